@@ -0,0 +1,46 @@
+//! Measures lexer generation time for a large token specification.
+//!
+//! Run with `cargo bench --bench large_spec`.
+
+use std::time::Instant;
+
+use jlek::TokenSpec;
+
+/// Builds a specification resembling a large language: hundreds of keyword tokens, a token
+/// matching any of the keywords (which yields a DFA with many states) and a few generic tokens.
+fn large_spec(num_of_keywords: usize) -> Vec<TokenSpec> {
+    let keywords = (0..num_of_keywords).map(keyword).collect::<Vec<String>>();
+    let mut token_specs = keywords
+        .iter()
+        .enumerate()
+        .map(|(i, keyword)| TokenSpec::new(format!("Keyword{i}"), keyword.clone()))
+        .collect::<Vec<TokenSpec>>();
+    token_specs.push(TokenSpec::new("AnyKeyword".to_string(), keywords.join("|")));
+    token_specs.push(TokenSpec::new("Number".to_string(), "\\d\\d*".to_string()));
+    token_specs.push(TokenSpec::new(
+        "Identifier".to_string(),
+        "(\\w|\\W)(\\w|\\W|\\d)*".to_string(),
+    ));
+    token_specs
+}
+
+/// Spells `i` in base 26 using lowercase letters, prefixed to avoid clashing prefixes.
+fn keyword(mut i: usize) -> String {
+    let mut keyword = String::from("kw");
+    loop {
+        keyword.push((b'a' + (i % 26) as u8) as char);
+        i /= 26;
+        if i == 0 {
+            break keyword;
+        }
+    }
+}
+
+fn main() {
+    for num_of_keywords in [100, 200, 400, 800] {
+        let token_specs = large_spec(num_of_keywords);
+        let start = Instant::now();
+        jlek::generate_to_string(&token_specs).unwrap();
+        println!("{:4} tokens: {:?}", token_specs.len(), start.elapsed());
+    }
+}
@@ -0,0 +1,254 @@
+use std::path::{Path, PathBuf};
+
+use crate::{Error, Options, Separators, TokenSpec, regex_parser};
+
+/// Builder for generating a lexer from a build script.
+///
+/// Token specifications are added with [Builder::token_spec] or read from spec files with
+/// [Builder::spec_file]. By default, the lexer is generated into `$OUT_DIR`, the directory Cargo
+/// provides to build scripts for generated files, and is included in the crate with
+/// [include!]:
+///
+/// ```ignore
+/// // build.rs
+/// fn main() {
+///     jlek::Builder::new()
+///         .spec_file("src/tokens.jlek")
+///         .build()
+///         .unwrap();
+/// }
+///
+/// // src/main.rs
+/// mod lexer {
+///     include!(concat!(env!("OUT_DIR"), "/lexer.rs"));
+/// }
+/// ```
+///
+/// Unlike generating into `src/`, this keeps the generated lexer out of the working tree.
+///
+/// # Spec file format
+///
+/// Each non-empty line of a spec file contains the identifier of a token specification, followed
+/// by whitespace and its regular expression pattern. Lines starting with `#` are comments:
+///
+/// ```text
+/// # keywords come first, as they take priority over identifiers
+/// If          if
+/// Number      \d\d*
+/// Identifier  \w(\w|\d)*
+/// ```
+///
+/// A `%separators` line sets the characters skipped between tokens, overriding
+/// [Options::separators]. It is followed by `whitespace`, `none`, a character class such as
+/// `( |\t)`, or `whitespace except` and a character class:
+///
+/// ```text
+/// %separators whitespace except \n
+/// Newline     \n
+/// ```
+#[derive(Default)]
+pub struct Builder {
+    sources: Vec<Source>,
+    options: Options,
+    output_directory: Option<PathBuf>,
+}
+
+enum Source {
+    TokenSpec(TokenSpec),
+    SpecFile(PathBuf),
+}
+
+impl Builder {
+    /// Creates a builder without token specifications and with default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a token specification. See [TokenSpec::new].
+    pub fn token_spec(mut self, name: &str, pattern: &str) -> Self {
+        let token_spec = TokenSpec::new(name.to_string(), pattern.to_string());
+        self.sources.push(Source::TokenSpec(token_spec));
+        self
+    }
+
+    /// Adds token specifications.
+    pub fn token_specs(mut self, token_specs: Vec<TokenSpec>) -> Self {
+        self.sources
+            .extend(token_specs.into_iter().map(Source::TokenSpec));
+        self
+    }
+
+    /// Adds the token specifications read from the spec file at `path` when building. Cargo is
+    /// instructed to rerun the build script when the file changes.
+    ///
+    /// Token specifications are ordered as added, regardless of whether they come from a spec file
+    /// or not.
+    pub fn spec_file(mut self, path: impl AsRef<Path>) -> Self {
+        self.sources
+            .push(Source::SpecFile(path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Sets the generation options.
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets the directory the lexer is generated into, defaults to `$OUT_DIR`.
+    pub fn output_directory(mut self, output_directory: impl AsRef<Path>) -> Self {
+        self.output_directory = Some(output_directory.as_ref().to_path_buf());
+        self
+    }
+
+    /// Generates the lexer.
+    ///
+    /// Fails with [Error::MissingOutputDirectory] if no output directory is set and `$OUT_DIR` is
+    /// not defined, i.e. when not called from a build script.
+    pub fn build(self) -> Result<(), Error> {
+        let output_directory = match self.output_directory {
+            Some(output_directory) => output_directory,
+            None => std::env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or(Error::MissingOutputDirectory)?,
+        };
+        let mut options = self.options;
+        let mut token_specs = vec![];
+        for source in self.sources {
+            match source {
+                Source::TokenSpec(token_spec) => token_specs.push(token_spec),
+                Source::SpecFile(path) => {
+                    println!("cargo:rerun-if-changed={}", path.display());
+                    let spec_file = read_spec_file(&path)?;
+                    token_specs.extend(spec_file.token_specs);
+                    if let Some(separators) = spec_file.separators {
+                        options = options.separators(separators);
+                    }
+                }
+            }
+        }
+        options.generate(&token_specs, &output_directory)
+    }
+}
+
+/// Content of a spec file.
+struct SpecFile {
+    token_specs: Vec<TokenSpec>,
+    separators: Option<Separators>,
+}
+
+fn read_spec_file(path: &Path) -> Result<SpecFile, Error> {
+    let content = std::fs::read_to_string(path)?;
+    let mut spec_file = SpecFile {
+        token_specs: vec![],
+        separators: None,
+    };
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = |message: String| Error::InvalidSpecFile {
+            path: path.to_path_buf(),
+            line: i + 1,
+            message,
+        };
+        if let Some(directive) = line.strip_prefix('%') {
+            let (name, value) = directive
+                .split_once(char::is_whitespace)
+                .unwrap_or((directive, ""));
+            if name != "separators" {
+                return Err(invalid(format!("unknown directive `%{name}`")));
+            }
+            spec_file.separators = Some(parse_separators(value.trim_start()).map_err(invalid)?);
+            continue;
+        }
+        let Some((name, pattern)) = line.split_once(char::is_whitespace) else {
+            return Err(invalid(
+                "expected a token name followed by a pattern".to_string(),
+            ));
+        };
+        spec_file.token_specs.push(TokenSpec::new(
+            name.to_string(),
+            pattern.trim_start().to_string(),
+        ));
+    }
+    Ok(spec_file)
+}
+
+/// Parses the value of a `%separators` line.
+fn parse_separators(value: &str) -> Result<Separators, String> {
+    match value {
+        "whitespace" => Ok(Separators::Whitespace),
+        "none" => Ok(Separators::None),
+        _ => match value.strip_prefix("whitespace except ") {
+            Some(pattern) => {
+                let pattern = pattern.trim_start();
+                regex_parser::parse_char_class(pattern)?;
+                Ok(Separators::WhitespaceExcept(pattern.to_string()))
+            }
+            None => {
+                regex_parser::parse_char_class(value)?;
+                Ok(Separators::Class(value.to_string()))
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Builder, Error};
+
+    #[test]
+    fn spec_file() {
+        let output_directory = crate::test_output_directory("builder");
+        let spec_file = output_directory.join("tokens.jlek");
+        std::fs::write(&spec_file, "# comment\n\nIf  if\nNumber \\d\\d*\n").unwrap();
+        Builder::new()
+            .spec_file(&spec_file)
+            .token_spec("Identifier", "\\w\\w*")
+            .options(crate::Options::new().standalone(true))
+            .output_directory(&output_directory)
+            .build()
+            .unwrap();
+        let content = std::fs::read_to_string(output_directory.join("lexer.rs")).unwrap();
+        assert!(
+            content.contains("pub enum TerminalClass {\n    If,\n    Number,\n    Identifier,")
+        );
+
+        std::fs::write(&spec_file, "If  if\nNumber\n").unwrap();
+        let result = Builder::new()
+            .spec_file(&spec_file)
+            .output_directory(&output_directory)
+            .build();
+        assert!(matches!(
+            result,
+            Err(Error::InvalidSpecFile { line: 2, .. })
+        ));
+
+        std::fs::write(
+            &spec_file,
+            "%separators whitespace except \\n\nNewline \\n\n",
+        )
+        .unwrap();
+        Builder::new()
+            .spec_file(&spec_file)
+            .output_directory(&output_directory)
+            .build()
+            .unwrap();
+        let content = std::fs::read_to_string(output_directory.join("lexer.rs")).unwrap();
+        assert!(content.contains("c.is_whitespace() && !matches!(c, '\\n')"));
+
+        for line in ["%separators \\n*", "%separator none"] {
+            std::fs::write(&spec_file, format!("If  if\n{line}\n")).unwrap();
+            let result = Builder::new()
+                .spec_file(&spec_file)
+                .output_directory(&output_directory)
+                .build();
+            assert!(matches!(
+                result,
+                Err(Error::InvalidSpecFile { line: 2, .. })
+            ));
+        }
+    }
+}
@@ -1,12 +1,13 @@
 use std::{
-    fs::{File, OpenOptions},
-    io::Write,
+    fs::File,
+    io::{BufRead, BufReader, Write},
     path::{Path, PathBuf},
 };
 
 use crate::{
-    TokenSpec,
+    Backend, Error, Options, Separators, TokenSpec,
     lexer_spec::{LexerSpec, State},
+    regex_parser::{self, CharRange},
 };
 
 /// Generates a lexer from token specifications.
@@ -14,377 +15,1511 @@ use crate::{
 /// This function will generate a Rust module (`lexer.rs`) at the `output_directory` for lexing
 /// input strings into tokens specified in `token_specs`. Note that `lexer.rs` depends on the
 /// `symbol.rs` module generated by [JJIK](https://github.com/fuad1502/JJIK), which contains the
-/// definition for tokens (`struct Terminal`).
+/// definition for tokens (`struct Terminal`), unless [Options::standalone] is set.
+///
+/// The generated file starts with a header containing a fingerprint of `token_specs`, the
+/// generation options and the JLEK version. If `lexer.rs` already exists with the same
+/// fingerprint, it is left untouched, which avoids needlessly recompiling its dependents.
+///
+/// In a build script, prefer [Builder](crate::Builder), which generates into `$OUT_DIR`.
 ///
 /// # Example
 ///
 /// ```rust
-/// use std::path::PathBuf;
-///
 /// // create a token specification for decimal numbers
 /// let number = jlek::TokenSpec::new("Number".to_string(), "\\d\\d*".to_string());
 /// let token_specs = vec![number];
 ///
 /// // generate `lexer.rs` at `output_directory`
-/// let output_directory = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src");
+/// let output_directory = std::env::temp_dir();
 /// jlek::generate(&token_specs, &output_directory).unwrap();
 /// ```
-pub fn generate(
+pub fn generate(token_specs: &Vec<TokenSpec>, output_directory: &Path) -> Result<(), Error> {
+    Options::new().generate(token_specs, output_directory)
+}
+
+/// Generates a lexer from token specifications into `writer`.
+///
+/// This function writes the content of the lexer module generated by [generate], e.g. for
+/// generating into standard output.
+pub fn generate_to_writer(
     token_specs: &Vec<TokenSpec>,
-    output_directory: &Path,
-) -> Result<(), std::io::Error> {
-    CodeGen::new(token_specs, output_directory)?.generate()
+    writer: &mut impl Write,
+) -> Result<(), Error> {
+    Options::new().generate_to_writer(token_specs, writer)
+}
+
+/// Generates a lexer from token specifications into a string.
+///
+/// This function returns the content of the lexer module generated by [generate], e.g. for
+/// snapshot testing.
+pub fn generate_to_string(token_specs: &Vec<TokenSpec>) -> Result<String, Error> {
+    Options::new().generate_to_string(token_specs)
+}
+
+impl Options {
+    /// Generates a lexer from token specifications using these options.
+    ///
+    /// See [generate] for details. The lexer is generated into [Options::file_name] instead of
+    /// `lexer.rs`.
+    pub fn generate(
+        &self,
+        token_specs: &Vec<TokenSpec>,
+        output_directory: &Path,
+    ) -> Result<(), Error> {
+        let file_path = PathBuf::from(output_directory).join(&self.file_name);
+        if is_up_to_date(&file_path, &header(token_specs, self)) {
+            return Ok(());
+        }
+        let lexer = self.generate_to_string(token_specs)?;
+        Ok(std::fs::write(file_path, lexer)?)
+    }
+
+    /// Generates a lexer from token specifications into `writer` using these options.
+    ///
+    /// See [generate_to_writer] for details.
+    pub fn generate_to_writer(
+        &self,
+        token_specs: &Vec<TokenSpec>,
+        writer: &mut impl Write,
+    ) -> Result<(), Error> {
+        Ok(CodeGen::new(token_specs, writer, self)?.generate()?)
+    }
+
+    /// Generates a lexer from token specifications into a string using these options.
+    ///
+    /// See [generate_to_string] for details.
+    pub fn generate_to_string(&self, token_specs: &Vec<TokenSpec>) -> Result<String, Error> {
+        let mut lexer = vec![];
+        self.generate_to_writer(token_specs, &mut lexer)?;
+        Ok(String::from_utf8(lexer).expect("generated lexer is valid UTF-8"))
+    }
+}
+
+/// Creates the header comment identifying the generated file's inputs.
+fn header(token_specs: &Vec<TokenSpec>, options: &Options) -> String {
+    // 64-bit FNV-1a, which unlike `DefaultHasher` is stable across Rust versions.
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut feed = |bytes: &[u8]| {
+        for byte in bytes {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    };
+    feed(format!("{options:?}").as_bytes());
+    for token_spec in token_specs {
+        for field in [&token_spec.name, &token_spec.pattern] {
+            feed(&field.len().to_le_bytes());
+            feed(field.as_bytes());
+        }
+    }
+    format!(
+        "// @generated by JLEK {}, fingerprint {hash:016x}. Do not edit.",
+        env!("CARGO_PKG_VERSION")
+    )
+}
+
+/// Formats `range` as a pattern matching its characters.
+fn range_pattern(range: &CharRange) -> String {
+    if range.start == range.end {
+        format!("{:?}", range.start)
+    } else {
+        format!("{:?}..={:?}", range.start, range.end)
+    }
 }
 
-struct CodeGen {
-    file: File,
+fn is_up_to_date(file_path: &Path, header: &str) -> bool {
+    let Ok(file) = File::open(file_path) else {
+        return false;
+    };
+    let mut first_line = String::new();
+    match BufReader::new(file).read_line(&mut first_line) {
+        Ok(_) => first_line.trim_end() == header,
+        Err(_) => false,
+    }
+}
+
+struct CodeGen<W: Write> {
+    writer: W,
+    header: String,
+    options: Options,
+    paths: Paths,
+    token_names: Vec<String>,
+    /// States of the token DFAs for [Backend::Table], or of the DFA combining them for
+    /// [Backend::DirectCoded].
     states: Vec<State>,
     initial_states: Vec<usize>,
+    char_classes: Vec<(CharRange, usize)>,
+    separator_class: Vec<CharRange>,
+    num_of_classes: usize,
+    transition_table: Vec<Vec<Option<usize>>>,
 }
 
-impl CodeGen {
-    fn new(token_specs: &Vec<TokenSpec>, output_directory: &Path) -> Result<Self, std::io::Error> {
-        let file = Self::create_file_at("lexer.rs", output_directory)?;
-        let lexer_spec = LexerSpec::new(token_specs);
+/// Paths by which the generated lexer refers to the symbols and to the `alloc` items. They are
+/// written in full rather than imported, so that several lexers can be generated into one module.
+struct Paths {
+    span: String,
+    terminal: String,
+    terminal_class: String,
+    vec: &'static str,
+    string: &'static str,
+    /// Prefix of the `vec!` and `format!` macros.
+    alloc: &'static str,
+}
+
+impl Paths {
+    fn new(options: &Options) -> Self {
+        let symbol_path = |name: &str| {
+            if options.standalone {
+                name.to_string()
+            } else {
+                format!("{}::{name}", options.symbol_module)
+            }
+        };
+        let (vec, string, alloc) = if options.no_std {
+            ("alloc::vec::Vec", "alloc::string::String", "alloc::")
+        } else {
+            ("Vec", "String", "")
+        };
+        Self {
+            span: symbol_path(&options.span_name),
+            terminal: symbol_path(&options.terminal_name),
+            terminal_class: symbol_path(&options.terminal_class_name),
+            vec,
+            string,
+            alloc,
+        }
+    }
+}
+
+impl<W: Write> CodeGen<W> {
+    fn new(token_specs: &Vec<TokenSpec>, writer: W, options: &Options) -> Result<Self, Error> {
+        // The classes of the tokens emitted by the lexer itself can't be told apart from a token of
+        // the same name.
+        let reserved_names: &[&str] = if options.error_recovery {
+            &["End", "Error"]
+        } else {
+            &["End"]
+        };
+        if let Some(token_spec) = token_specs
+            .iter()
+            .find(|token_spec| reserved_names.contains(&token_spec.name.as_str()))
+        {
+            return Err(Error::ReservedTokenName {
+                token: token_spec.name.clone(),
+            });
+        }
+        let lexer_spec = LexerSpec::new(token_specs, options)?;
+        let states = match options.backend {
+            Backend::Table => lexer_spec.states,
+            Backend::DirectCoded => lexer_spec.combined_states(options)?,
+        };
+        let separator_class = match &options.separators {
+            Separators::Class(pattern) | Separators::WhitespaceExcept(pattern) => {
+                regex_parser::parse_char_class(pattern)
+                    .map_err(|message| Error::InvalidSeparators { message })?
+            }
+            Separators::Whitespace | Separators::None => vec![],
+        };
+        let header = header(token_specs, options);
+        let mut token_names = vec![];
+        for token_spec in token_specs {
+            if !token_names.contains(&token_spec.name) {
+                token_names.push(token_spec.name.clone());
+            }
+        }
         Ok(Self {
-            file,
-            states: lexer_spec.states,
+            writer,
+            header,
+            options: options.clone(),
+            paths: Paths::new(options),
+            token_names,
+            states,
             initial_states: lexer_spec.initial_states,
+            char_classes: lexer_spec.char_classes,
+            separator_class,
+            num_of_classes: lexer_spec.num_of_classes,
+            transition_table: lexer_spec.transition_table,
         })
     }
 
-    fn create_file_at(name: &str, directory: &Path) -> Result<File, std::io::Error> {
-        let file_path = PathBuf::from(directory).join(name);
-        OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(file_path)
-    }
-
     fn generate(mut self) -> Result<(), std::io::Error> {
-        self.write_uses()?;
-        self.write_num_of_states()?;
+        self.write_header()?;
+        if self.options.standalone {
+            self.write_symbols()?;
+        }
+        self.write_line_column()?;
+        self.write_lex_error()?;
         self.write_structs()?;
         self.write_impl_header()?;
-        self.write_impl_new_header()?;
-        self.write_states()?;
-        self.write_initial_states()?;
-        self.write_transition_table()?;
-        self.write_impl_new_footer()?;
+        if self.options.backend == Backend::Table {
+            self.write_states()?;
+            self.write_initial_states()?;
+            self.write_num_of_states()?;
+            self.write_char_classes()?;
+            self.write_transition_table()?;
+        }
+        self.write_impl_new()?;
         self.write_impl_others()?;
-        self.write_impl_footer()
+        self.write_is_separator()?;
+        if self.options.backend == Backend::Table {
+            self.write_table_next_state()?;
+        }
+        self.write_impl_footer()?;
+        self.write_impl_iterator()?;
+        self.write_driver()?;
+        if !self.options.no_std {
+            self.write_stream()?;
+        }
+        Ok(())
     }
 
-    fn write_uses(&mut self) -> Result<(), std::io::Error> {
+    fn write_header(&mut self) -> Result<(), std::io::Error> {
+        writeln!(self.writer, "{}", self.header)
+    }
+
+    fn write_symbols(&mut self) -> Result<(), std::io::Error> {
+        let terminal_class = &self.paths.terminal_class;
+        writeln!(
+            self.writer,
+            "\n#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]"
+        )?;
         writeln!(
-            self.file,
-            r#"use std::{{collections::HashMap, fs::File, io::Read, path::Path}};
+            self.writer,
+            "pub enum {} {{",
+            self.options.terminal_class_name
+        )?;
+        for token_name in &self.token_names {
+            Self::write_tab(&mut self.writer, 1)?;
+            writeln!(self.writer, "{token_name},")?;
+        }
+        Self::write_tab(&mut self.writer, 1)?;
+        writeln!(self.writer, "End,")?;
+        if self.options.error_recovery {
+            Self::write_tab(&mut self.writer, 1)?;
+            writeln!(self.writer, "Error,")?;
+        }
+        writeln!(self.writer, "}}")?;
+        let (terminal, span) = (&self.options.terminal_name, &self.options.span_name);
+        writeln!(
+            self.writer,
+            r#"
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct {span} {{
+    start_pos: usize,
+    end_pos: usize,
+}}
+
+impl {span} {{
+    pub fn new(start_pos: usize, end_pos: usize) -> Self {{
+        Self {{ start_pos, end_pos }}
+    }}
+
+    pub fn start_pos(&self) -> usize {{
+        self.start_pos
+    }}
+
+    pub fn end_pos(&self) -> usize {{
+        self.end_pos
+    }}
+}}
 
-use crate::symbol::{{Span, Terminal, TerminalClass}};
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct {terminal} {{
+    class: {terminal_class},
+    span: {span},
+}}
+
+impl {terminal} {{
+    pub fn new(class: {terminal_class}, span: {span}) -> Self {{
+        Self {{ class, span }}
+    }}
+
+    pub fn class(&self) -> {terminal_class} {{
+        self.class
+    }}
+
+    pub fn span(&self) -> &{span} {{
+        &self.span
+    }}
+}}
 "#
         )
     }
 
     fn write_num_of_states(&mut self) -> Result<(), std::io::Error> {
+        Self::write_tab(&mut self.writer, 1)?;
         writeln!(
-            self.file,
-            "static NUM_OF_STATES: usize = {};",
+            self.writer,
+            "const NUM_OF_STATES: usize = {};",
             self.states.len()
         )
     }
 
-    fn write_structs(&mut self) -> Result<(), std::io::Error> {
-        writeln!(
-            self.file,
+    fn write_line_column(&mut self) -> Result<(), std::io::Error> {
+        let lexer = &self.options.lexer_name;
+        write!(
+            self.writer,
             r#"
-#[derive(Copy, Clone)]
-struct State {{
-    class: Option<TerminalClass>,
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct {lexer}LineColumn {{
+    pub line: usize,
+    pub column: usize,
 }}
 
-pub struct Lexer {{
-    chars: Vec<u8>,
-    line_start_indices: Vec<usize>,
-    start_pos: usize,
-    current_pos: usize,
-    current_token: Option<Terminal>,
-    states: [State; NUM_OF_STATES],
-    transition_table: Vec<HashMap<char, usize>>,
-    states_stack: Vec<Vec<usize>>,
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum {lexer}ColumnUnit {{
+    Char,
+    Utf16,
 }}
 "#
         )
     }
 
-    fn write_impl_header(&mut self) -> Result<(), std::io::Error> {
-        writeln!(self.file, "impl Lexer {{")
-    }
+    fn write_lex_error(&mut self) -> Result<(), std::io::Error> {
+        let lexer = &self.options.lexer_name;
+        let span = &self.paths.span;
+        // Without `std`, the input is never read through `std::io`, hence there are no I/O errors.
+        let (std, io_kind, io_display) = if self.options.no_std {
+            ("core", "", String::new())
+        } else {
+            (
+                "std",
+                "\n    Io(std::io::Error),",
+                format!("\n            {lexer}ErrorKind::Io(error) => write!(f, \"{{error}}\")?,"),
+            )
+        };
+        write!(
+            self.writer,
+            r#"
+#[derive(Debug)]
+pub struct {lexer}Error {{
+    kind: {lexer}ErrorKind,
+    start_pos: usize,
+    end_pos: usize,
+    line: usize,
+    column: usize,
+}}
 
-    fn write_impl_footer(&mut self) -> Result<(), std::io::Error> {
-        writeln!(self.file, "}}")
-    }
+#[derive(Debug)]
+pub enum {lexer}ErrorKind {{
+    UnexpectedChar(char),
+    InvalidUtf8,{io_kind}
+}}
+
+impl {lexer}Error {{
+    pub fn kind(&self) -> &{lexer}ErrorKind {{
+        &self.kind
+    }}
+
+    pub fn span(&self) -> {span} {{
+        {span}::new(self.start_pos, self.end_pos)
+    }}
+
+    pub fn found(&self) -> Option<char> {{
+        match self.kind {{
+            {lexer}ErrorKind::UnexpectedChar(c) => Some(c),
+            _ => None,
+        }}
+    }}
+
+    pub fn byte_pos(&self) -> usize {{
+        self.start_pos
+    }}
+
+    pub fn line(&self) -> usize {{
+        self.line
+    }}
+
+    pub fn column(&self) -> usize {{
+        self.column
+    }}
+}}
 
-    fn write_impl_new_header(&mut self) -> Result<(), std::io::Error> {
+impl {std}::fmt::Display for {lexer}Error {{
+    fn fmt(&self, f: &mut {std}::fmt::Formatter<'_>) -> {std}::fmt::Result {{
+        match &self.kind {{
+            {lexer}ErrorKind::UnexpectedChar(c) => write!(f, "unexpected character {{c:?}}")?,
+            {lexer}ErrorKind::InvalidUtf8 => write!(f, "invalid UTF-8")?,{io_display}
+        }}
+        write!(f, " at line {{}}, column {{}}", self.line, self.column)
+    }}
+}}
+"#
+        )?;
+        if self.options.no_std {
+            return writeln!(
+                self.writer,
+                "\nimpl core::error::Error for {lexer}Error {{}}"
+            );
+        }
         write!(
-            self.file,
-            r#"    pub fn from_source_str(source: &str) -> Self {{
-        let chars = source.chars().map(|c| c as u8).collect::<Vec<u8>>();
-        let mut line_start_indices = chars
-            .iter()
-            .enumerate()
-            .filter_map(|(i, c)| if *c == b'\n' {{ Some(i + 1) }} else {{ None }})
-            .collect::<Vec<usize>>();
-        line_start_indices.insert(0, 0);
+            self.writer,
+            r#"
+impl std::error::Error for {lexer}Error {{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {{
+        match &self.kind {{
+            {lexer}ErrorKind::Io(error) => Some(error),
+            _ => None,
+        }}
+    }}
+}}
+"#
+        )
+    }
+
+    fn write_structs(&mut self) -> Result<(), std::io::Error> {
+        let Paths {
+            terminal_class,
+            vec,
+            ..
+        } = &self.paths;
+        let lexer = &self.options.lexer_name;
+        if self.options.backend == Backend::Table {
+            write!(
+                self.writer,
+                r#"
+#[derive(Copy, Clone)]
+struct {lexer}State {{
+    class: Option<{terminal_class}>,
+}}
+"#
+            )?;
+        }
+        writeln!(
+            self.writer,
+            r#"
+pub struct {lexer}<'src> {{
+    source: &'src str,
+    driver: {lexer}Driver<&'src str>,
+    line_start_indices: {vec}<usize>,
+    context_lines: usize,
+    color: bool,
+}}
 "#
         )
     }
 
+    fn write_impl_header(&mut self) -> Result<(), std::io::Error> {
+        writeln!(
+            self.writer,
+            "impl<'src> {}<'src> {{",
+            self.options.lexer_name
+        )
+    }
+
+    fn write_impl_footer(&mut self) -> Result<(), std::io::Error> {
+        writeln!(self.writer, "}}")
+    }
+
     fn write_states(&mut self) -> Result<(), std::io::Error> {
-        Self::write_tab(&mut self.file, 2)?;
-        writeln!(self.file, "let states = [")?;
+        let terminal_class = &self.paths.terminal_class;
+        let lexer = &self.options.lexer_name;
+        Self::write_tab(&mut self.writer, 1)?;
+        writeln!(self.writer, "const STATES: &'static [{lexer}State] = &[")?;
         for state in &self.states {
-            Self::write_tab(&mut self.file, 3)?;
+            Self::write_tab(&mut self.writer, 2)?;
             match &state.accepts {
-                None => writeln!(self.file, "State {{ class: None }},")?,
+                None => writeln!(self.writer, "{lexer}State {{ class: None }},")?,
                 Some(token_name) => writeln!(
-                    self.file,
-                    "State {{ class: Some(TerminalClass::{token_name}) }},"
+                    self.writer,
+                    "{lexer}State {{ class: Some({terminal_class}::{token_name}) }},"
                 )?,
             }
         }
-        Self::write_tab(&mut self.file, 2)?;
-        writeln!(self.file, "];")
+        Self::write_tab(&mut self.writer, 1)?;
+        writeln!(self.writer, "];")
     }
 
     fn write_initial_states(&mut self) -> Result<(), std::io::Error> {
-        write!(self.file, "        let initial_states = vec![")?;
+        write!(
+            self.writer,
+            "    const INITIAL_STATES: &'static [usize] = &["
+        )?;
         for (i, state) in self.initial_states.iter().enumerate() {
-            write!(self.file, "{state}")?;
+            write!(self.writer, "{state}")?;
             if i != self.initial_states.len() - 1 {
-                write!(self.file, ", ")?;
+                write!(self.writer, ", ")?;
             }
         }
-        writeln!(self.file, "];")
+        writeln!(self.writer, "];")
     }
 
-    fn write_transition_table(&mut self) -> Result<(), std::io::Error> {
-        for (id, state) in self.states.iter().enumerate() {
-            Self::write_tab(&mut self.file, 2)?;
-            let mut_attr = if state.next.is_empty() { "" } else { "mut" };
+    fn write_char_classes(&mut self) -> Result<(), std::io::Error> {
+        let mut ascii_classes = [0; 128];
+        for (range, class) in &self.char_classes {
+            for ch in range.start..=range.end.min('\x7F') {
+                ascii_classes[ch as usize] = *class;
+            }
+        }
+        Self::write_tab(&mut self.writer, 1)?;
+        writeln!(
+            self.writer,
+            "const ASCII_CLASSES: &'static [usize; 128] = &["
+        )?;
+        for row in ascii_classes.chunks(16) {
+            Self::write_tab(&mut self.writer, 2)?;
+            let row = row.iter().map(|c| c.to_string()).collect::<Vec<String>>();
+            writeln!(self.writer, "{},", row.join(", "))?;
+        }
+        Self::write_tab(&mut self.writer, 1)?;
+        writeln!(self.writer, "];")?;
+
+        Self::write_tab(&mut self.writer, 1)?;
+        writeln!(
+            self.writer,
+            "const CHAR_CLASSES: &'static [(char, char, usize)] = &["
+        )?;
+        for (range, class) in &self.char_classes {
+            Self::write_tab(&mut self.writer, 2)?;
             writeln!(
-                self.file,
-                "let {mut_attr} state_{id}_transitions = HashMap::new();"
+                self.writer,
+                "({:?}, {:?}, {class}),",
+                range.start, range.end
             )?;
-            for (ch, next) in &state.next {
-                Self::write_tab(&mut self.file, 2)?;
-                let esc = match *ch {
-                    '\\' | '\'' => "\\",
-                    _ => "",
-                };
-                writeln!(
-                    self.file,
-                    "state_{id}_transitions.insert('{esc}{ch}', {next});"
-                )?;
-            }
         }
+        Self::write_tab(&mut self.writer, 1)?;
+        writeln!(self.writer, "];")
+    }
 
-        Self::write_tab(&mut self.file, 2)?;
-        writeln!(self.file, "let transition_table = vec![")?;
-        for id in 0..self.states.len() {
-            Self::write_tab(&mut self.file, 3)?;
-            writeln!(self.file, "state_{id}_transitions,")?;
+    fn write_transition_table(&mut self) -> Result<(), std::io::Error> {
+        Self::write_tab(&mut self.writer, 1)?;
+        writeln!(
+            self.writer,
+            "const TRANSITION_TABLE: &'static [[usize; {}]] = &[",
+            self.num_of_classes
+        )?;
+        for class_next in &self.transition_table {
+            Self::write_tab(&mut self.writer, 2)?;
+            write!(self.writer, "[")?;
+            for (i, next) in class_next.iter().enumerate() {
+                // A next state of `NUM_OF_STATES` denotes the absence of a transition.
+                write!(self.writer, "{}", next.unwrap_or(self.states.len()))?;
+                if i != class_next.len() - 1 {
+                    write!(self.writer, ", ")?;
+                }
+            }
+            writeln!(self.writer, "],")?;
         }
-        Self::write_tab(&mut self.file, 2)?;
-        writeln!(self.file, "];")
+        Self::write_tab(&mut self.writer, 1)?;
+        writeln!(self.writer, "];")
     }
 
-    fn write_impl_new_footer(&mut self) -> Result<(), std::io::Error> {
+    fn write_impl_new(&mut self) -> Result<(), std::io::Error> {
+        let vec = self.paths.vec;
+        let lexer = &self.options.lexer_name;
+        let color = if self.options.no_std {
+            "false"
+        } else {
+            "std::env::var_os(\"NO_COLOR\").is_none_or(|no_color| no_color.is_empty())"
+        };
+        // The source is read by the caller, as the lexer borrows it.
+        let read_source = if self.options.no_std {
+            ""
+        } else {
+            r#"
+    pub fn read_source(source_file: &std::path::Path) -> Result<String, std::io::Error> {
+        std::fs::read_to_string(source_file)
+    }
+"#
+        };
         write!(
-            self.file,
+            self.writer,
             r#"
+    pub fn from_source_str(source: &'src str) -> Self {{
+        let mut line_start_indices = source
+            .bytes()
+            .enumerate()
+            .filter_map(|(i, c)| if c == b'\n' {{ Some(i + 1) }} else {{ None }})
+            .collect::<{vec}<usize>>();
+        line_start_indices.insert(0, 0);
         Self {{
-            chars,
+            source,
+            driver: {lexer}Driver::new(source),
             line_start_indices,
-            start_pos: 0,
-            current_pos: 0,
-            current_token: None,
-            states,
-            transition_table,
-            states_stack: vec![initial_states],
+            context_lines: 0,
+            color: {color},
         }}
     }}
-            "#
+{read_source}
+    pub fn with_tab_width(mut self, tab_width: usize) -> Self {{
+        self.driver.tab_width = tab_width.max(1);
+        self
+    }}
+
+    pub fn with_column_unit(mut self, column_unit: {lexer}ColumnUnit) -> Self {{
+        self.driver.column_unit = column_unit;
+        self
+    }}
+
+    pub fn with_context_lines(mut self, context_lines: usize) -> Self {{
+        self.context_lines = context_lines;
+        self
+    }}
+
+    pub fn with_color(mut self, color: bool) -> Self {{
+        self.color = color;
+        self
+    }}
+"#
         )
     }
 
     fn write_impl_others(&mut self) -> Result<(), std::io::Error> {
+        let Paths {
+            span,
+            terminal,
+            vec,
+            string,
+            alloc,
+            ..
+        } = &self.paths;
+        let lexer = &self.options.lexer_name;
+        let errors = self.errors_getter();
         write!(
-            self.file,
+            self.writer,
             r#"
-    pub fn new(source_file: &Path) -> Result<Self, std::io::Error> {{
-        let mut source_file = File::open(source_file)?;
-        let mut source = String::new();
-        let _ = source_file.read_to_string(&mut source)?;
-        Ok(Self::from_source_str(&source))
+    pub fn next_token(&mut self) -> Result<{terminal}, {lexer}Error> {{
+        self.driver.next_token()
     }}
 
-    pub fn next_token(&mut self) -> Result<Terminal, String> {{
+    pub fn peek_token(&mut self) -> Result<&{terminal}, {lexer}Error> {{
+        self.driver.peek_token()
+    }}
+
+    pub fn tokenize_all(&mut self) -> Result<{vec}<{terminal}>, {lexer}Error> {{
+        self.by_ref().collect()
+    }}
+{errors}
+    pub fn get_lexeme(&self, token: &{terminal}) -> &'src str {{
+        self.str_from_span(token.span())
+    }}
+
+    pub fn str_from_span(&self, span: &{span}) -> &'src str {{
+        &self.source[span.start_pos()..span.end_pos()]
+    }}
+
+    pub fn line_column(&self, pos: usize) -> {lexer}LineColumn {{
+        let line = self.line_start_indices.partition_point(|&i| i <= pos);
+        let line_start_idx = self.line_start_indices[line - 1];
+        self.source[line_start_idx..pos]
+            .chars()
+            .fold({lexer}LineColumn {{ line, column: 1 }}, |line_column, c| {{
+                self.driver.advance(line_column, c)
+            }})
+    }}
+
+    pub fn span_line_column(&self, span: &{span}) -> ({lexer}LineColumn, {lexer}LineColumn) {{
+        (
+            self.line_column(span.start_pos()),
+            self.line_column(span.end_pos()),
+        )
+    }}
+
+    pub fn show_span(&self, span: &{span}) -> {string} {{
+        let (blue, red, end) = self.colors();
+        let first_line = self
+            .line_start_indices
+            .partition_point(|&i| i <= span.start_pos());
+        let last_line = self
+            .line_start_indices
+            .partition_point(|&i| i < span.end_pos())
+            .max(first_line);
+        let context_first_line = first_line.saturating_sub(self.context_lines).max(1);
+        let context_last_line =
+            (last_line + self.context_lines).min(self.line_start_indices.len());
+        let mut rendered_lines = {alloc}vec![];
+        for line_number in context_first_line..=context_last_line {{
+            let (line_start_idx, line) = self.line(line_number);
+            rendered_lines.push({alloc}format!("{{blue}}Line {{line_number:3}}|{{end}}{{line}}"));
+            if line_number != first_line && line_number != last_line {{
+                continue;
+            }}
+            let line_end_idx = line_start_idx + line.len();
+            let marker_start =
+                span.start_pos().clamp(line_start_idx, line_end_idx) - line_start_idx;
+            let marker_end = span.end_pos().clamp(line_start_idx, line_end_idx) - line_start_idx;
+            // Tabs are kept in the indentation, so that the marker lines up with the line.
+            let indent = line[..marker_start]
+                .chars()
+                .map(|c| if c == '\t' {{ '\t' }} else {{ ' ' }})
+                .collect::<{string}>();
+            let marked_length = line[marker_start..marker_end].chars().count();
+            let dashes = "-".repeat(marked_length.saturating_sub(1));
+            let marker = if line_number == first_line {{
+                {alloc}format!("^{{dashes}}")
+            }} else {{
+                {alloc}format!("{{dashes}}^")
+            }};
+            rendered_lines.push({alloc}format!("         {{red}}{{indent}}{{marker}}{{end}}"));
+        }}
+        rendered_lines.join("\n")
+    }}
+
+    pub fn show_error(&self, error: &{lexer}Error) -> {string} {{
+        let (_, red, end) = self.colors();
+        let span_str = self.show_span(&error.span());
+        {alloc}format!("{{span_str}}\n{{red}}error{{end}}: {{error}}")
+    }}
+
+    fn line(&self, line_number: usize) -> (usize, &'src str) {{
+        let line_start_idx = self.line_start_indices[line_number - 1];
+        let line_end_idx = match self.line_start_indices.get(line_number) {{
+            Some(idx) => idx - 1,
+            None => self.source.len(),
+        }};
+        let line = self.source[line_start_idx..line_end_idx].trim_end_matches('\r');
+        (line_start_idx, line)
+    }}
+
+    fn colors(&self) -> (&'static str, &'static str, &'static str) {{
+        if self.color {{
+            ("\x1B[34m", "\x1B[31m", "\x1B[0m")
+        }} else {{
+            ("", "", "")
+        }}
+    }}
+"#
+        )
+    }
+
+    fn write_is_separator(&mut self) -> Result<(), std::io::Error> {
+        let class = self
+            .separator_class
+            .iter()
+            .map(range_pattern)
+            .collect::<Vec<String>>()
+            .join(" | ");
+        let (parameter, body) = match &self.options.separators {
+            Separators::Whitespace => ("c", "c.is_whitespace()".to_string()),
+            Separators::None => ("_", "false".to_string()),
+            Separators::Class(_) => ("c", format!("matches!(c, {class})")),
+            Separators::WhitespaceExcept(_) => {
+                ("c", format!("c.is_whitespace() && !matches!(c, {class})"))
+            }
+        };
+        write!(
+            self.writer,
+            r#"
+    fn is_separator({parameter}: char) -> bool {{
+        {body}
+    }}
+"#
+        )
+    }
+
+    fn write_impl_iterator(&mut self) -> Result<(), std::io::Error> {
+        let terminal = &self.paths.terminal;
+        let lexer = &self.options.lexer_name;
+        write!(
+            self.writer,
+            r#"
+impl<'src> Iterator for {lexer}<'src> {{
+    type Item = Result<{terminal}, {lexer}Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {{
+        self.driver.next()
+    }}
+}}
+"#
+        )
+    }
+
+    fn write_driver(&mut self) -> Result<(), std::io::Error> {
+        let Paths {
+            span,
+            terminal,
+            terminal_class,
+            ..
+        } = &self.paths;
+        let lexer = &self.options.lexer_name;
+        let (states_field, states_init) = (self.states_field(), self.states_init());
+        let (errors_field, errors_init) = (self.errors_field(), self.errors_init());
+        let on_unexpected_char = if self.options.error_recovery {
+            "self.recover(found)"
+        } else {
+            &format!("Err(self.error({lexer}ErrorKind::UnexpectedChar(found)))")
+        };
+        write!(
+            self.writer,
+            r#"
+/// Input of a `{lexer}Driver`, indexed by byte offsets.
+trait {lexer}Input {{
+    /// Obtains the character at `pos`. The input before `start_pos`, the start of the current
+    /// token, is no longer needed.
+    fn char_at(&mut self, pos: usize, start_pos: usize) -> Result<Option<char>, {lexer}ErrorKind>;
+
+    /// Obtains the input from `start_pos` to `end_pos`, which must have been read and must not be
+    /// before the current token.
+    fn slice(&self, start_pos: usize, end_pos: usize) -> &str;
+}}
+
+impl {lexer}Input for &str {{
+    fn char_at(&mut self, pos: usize, _: usize) -> Result<Option<char>, {lexer}ErrorKind> {{
+        Ok(self[pos..].chars().next())
+    }}
+
+    fn slice(&self, start_pos: usize, end_pos: usize) -> &str {{
+        &self[start_pos..end_pos]
+    }}
+}}
+
+/// Lexes the tokens of an input, for both `{lexer}` and its streaming counterpart.
+struct {lexer}Driver<I> {{
+    input: I,
+    start_pos: usize,
+    start_line_column: {lexer}LineColumn,
+    current_pos: usize,
+    current_token: Option<{terminal}>,{states_field}
+    reached_end: bool,
+    finished: bool,
+    tab_width: usize,
+    column_unit: {lexer}ColumnUnit,{errors_field}
+}}
+
+impl<I: {lexer}Input> {lexer}Driver<I> {{
+    fn new(input: I) -> Self {{
+        Self {{
+            input,
+            start_pos: 0,
+            start_line_column: {lexer}LineColumn {{ line: 1, column: 1 }},
+            current_pos: 0,
+            current_token: None,{states_init}
+            reached_end: false,
+            finished: false,
+            tab_width: 1,
+            column_unit: {lexer}ColumnUnit::Char,{errors_init}
+        }}
+    }}
+
+    fn next_token(&mut self) -> Result<{terminal}, {lexer}Error> {{
         let token = self.peek_token()?.clone();
         self.move_start_pos();
         self.current_token = None;
         Ok(token)
     }}
 
-    pub fn peek_token(&mut self) -> Result<&Terminal, String> {{
+    fn peek_token(&mut self) -> Result<&{terminal}, {lexer}Error> {{
         if self.current_token.is_none() {{
-            self.skip_whitespaces();
-            if self.peek_char().is_none() {{
-                let end_token = Terminal::new(TerminalClass::End, self.current_span());
+            self.skip_separators()?;
+            if self.peek_char()?.is_none() {{
+                let end_token = {terminal}::new({terminal_class}::End, self.current_span());
                 self.current_token = Some(end_token);
+                self.reached_end = true;
             }} else {{
                 self.current_token = Some(self.get()?);
-                _ = self.states_stack.split_off(1);
             }}
         }}
         Ok(self.current_token.as_ref().unwrap())
     }}
 
-    pub fn get_lexeme(&self, token: &Terminal) -> &str {{
-        self.str_from_span(token.span())
-    }}
-
-    pub fn str_from_span(&self, span: &Span) -> &str {{
-        str::from_utf8(&self.chars[span.start_pos()..span.end_pos()]).unwrap()
+    /// Obtains the line and column following the character `c` at `line_column`.
+    fn advance(&self, line_column: {lexer}LineColumn, c: char) -> {lexer}LineColumn {{
+        let {lexer}LineColumn {{ line, column }} = line_column;
+        let column = match (c, self.column_unit) {{
+            ('\n', _) => return {lexer}LineColumn {{ line: line + 1, column: 1 }},
+            ('\t', {lexer}ColumnUnit::Char) => {{
+                (column - 1) / self.tab_width * self.tab_width + self.tab_width + 1
+            }}
+            (_, {lexer}ColumnUnit::Char) => column + 1,
+            (_, {lexer}ColumnUnit::Utf16) => column + c.len_utf16(),
+        }};
+        {lexer}LineColumn {{ line, column }}
     }}
 
-    pub fn show_span(&self, span: &Span) -> String {{
-        let line_number = self
-            .line_start_indices
-            .partition_point(|&i| i <= span.start_pos());
-        let line_start_idx = self.line_start_indices[line_number - 1];
-        let line_end_idx = match self.line_start_indices.get(line_number) {{
-            Some(idx) => idx - 1,
-            None => self.chars.len(),
-        }};
-        let line = &self.chars[line_start_idx..line_end_idx];
-        let line = str::from_utf8(line).unwrap();
-        let span_offset = span.start_pos() - line_start_idx;
-        let span_length = span.end_pos() - span.start_pos();
-        let span_marker = format!(
-            "{{}}{{}}{{}}",
-            " ".repeat(span_offset),
-            "^",
-            "-".repeat(span_length.saturating_sub(1))
-        );
-        let blue = "\x1B[34m";
-        let red = "\x1B[31m";
-        let end = "\x1B[0m";
-        format!("{{blue}}Line {{line_number:3}}|{{end}}{{line}}\n         {{red}}{{span_marker}}{{end}}")
+    /// Obtains the line and column of `pos`, which must not be before the current token.
+    fn line_column(&self, pos: usize) -> {lexer}LineColumn {{
+        self.input
+            .slice(self.start_pos, pos)
+            .chars()
+            .fold(self.start_line_column, |line_column, c| {{
+                self.advance(line_column, c)
+            }})
     }}
 
     fn move_start_pos(&mut self) {{
+        self.start_line_column = self.line_column(self.current_pos);
         self.start_pos = self.current_pos;
     }}
 
-    fn get(&mut self) -> Result<Terminal, String> {{
-        loop {{
-            match self.peek_char() {{
-                Some(c) => {{
-                    if self.move_states_on_stack(c) {{
-                        self.read_char();
-                    }} else {{
-                        return self.evaluate_stack();
-                    }}
-                }}
-                None => return self.evaluate_stack(),
+    fn get(&mut self) -> Result<{terminal}, {lexer}Error> {{
+        match self.longest_match()? {{
+            Some((class, end_pos)) => {{
+                self.current_pos = end_pos;
+                Ok({terminal}::new(class, self.current_span()))
+            }}
+            None => {{
+                let found = self.peek_char()?.unwrap();
+                {on_unexpected_char}
             }}
         }}
     }}
 
-    fn move_states_on_stack(&mut self, input: char) -> bool {{
-        let mut new_states = vec![];
-        for state in self.states_stack.last().unwrap() {{
-            if let Some(new_state) = self.transition_table[*state].get(&input) {{
-                new_states.push(*new_state);
-            }}
+    fn error(&self, kind: {lexer}ErrorKind) -> {lexer}Error {{
+        let end_pos = match kind {{
+            {lexer}ErrorKind::UnexpectedChar(c) => self.current_pos + c.len_utf8(),
+            _ => self.current_pos,
+        }};
+        let {lexer}LineColumn {{ line, column }} = self.line_column(self.current_pos);
+        {lexer}Error {{
+            kind,
+            start_pos: self.current_pos,
+            end_pos,
+            line,
+            column,
         }}
-        if !new_states.is_empty() {{
-            self.states_stack.push(new_states);
-            return true;
+    }}
+
+    fn peek_char(&mut self) -> Result<Option<char>, {lexer}Error> {{
+        self.input
+            .char_at(self.current_pos, self.start_pos)
+            .map_err(|kind| self.error(kind))
+    }}
+
+    fn read_char(&mut self) -> Result<Option<char>, {lexer}Error> {{
+        let ch = self.peek_char()?;
+        if let Some(ch) = ch {{
+            self.current_pos += ch.len_utf8();
         }}
-        false
+        Ok(ch)
     }}
 
-    fn evaluate_stack(&mut self) -> Result<Terminal, String> {{
-        loop {{
-            let mut accepting_classes = vec![];
-            for state in self.states_stack.last().unwrap() {{
-                if let Some(class) = self.states[*state].class {{
-                    accepting_classes.push(class);
-                }}
-            }}
-            if let Some(prioritized_class) = accepting_classes.iter().copied().min() {{
-                let span = self.current_span();
-                let class = prioritized_class;
-                return Ok(Terminal::new(class, span));
-            }} else if self.states_stack.len() == 1 {{
-                return Err(self.report_error());
+    fn skip_separators(&mut self) -> Result<(), {lexer}Error> {{
+        while let Some(c) = self.peek_char()? {{
+            if {lexer}::is_separator(c) {{
+                self.read_char()?;
             }} else {{
-                self.states_stack.pop();
-                self.revert_char();
+                break;
             }}
         }}
+        self.move_start_pos();
+        Ok(())
     }}
 
-    fn report_error(&self) -> String {{
-        let span_str = self.show_span(&self.current_span());
-        let red = "\x1B[31m";
-        let end = "\x1B[0m";
-        format!(
-            "{{span_str}}\n{{red}}error{{end}}: unexpected character found: {{}}",
-            self.peek_char()
-                .map(|c| c.to_string())
-                .unwrap_or(String::from("EOF"))
+    fn current_span(&self) -> {span} {{
+        {span}::new(self.start_pos, self.current_pos)
+    }}
+"#
+        )?;
+        match self.options.backend {
+            Backend::Table => self.write_table_longest_match()?,
+            Backend::DirectCoded => self.write_direct_coded_longest_match()?,
+        }
+        if self.options.error_recovery {
+            self.write_recover()?;
+        }
+        let (lexer, terminal) = (&self.options.lexer_name, &self.paths.terminal);
+        write!(
+            self.writer,
+            r#"}}
+
+impl<I: {lexer}Input> Iterator for {lexer}Driver<I> {{
+    type Item = Result<{terminal}, {lexer}Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {{
+        if self.finished {{
+            return None;
+        }}
+        let token = self.next_token();
+        self.finished = self.reached_end || token.is_err();
+        Some(token)
+    }}
+}}
+"#
         )
+    }
+
+    fn write_recover(&mut self) -> Result<(), std::io::Error> {
+        let Paths {
+            terminal,
+            terminal_class,
+            ..
+        } = &self.paths;
+        let lexer = &self.options.lexer_name;
+        write!(
+            self.writer,
+            r#"
+    /// Skips the maximal run of characters from which no token can be lexed.
+    fn recover(&mut self, found: char) -> Result<{terminal}, {lexer}Error> {{
+        let mut error = self.error({lexer}ErrorKind::UnexpectedChar(found));
+        self.read_char()?;
+        while let Some(c) = self.peek_char()? {{
+            if {lexer}::is_separator(c) || self.is_lexable()? {{
+                break;
+            }}
+            self.read_char()?;
+        }}
+        error.end_pos = self.current_pos;
+        self.errors.push(error);
+        Ok({terminal}::new({terminal_class}::Error, self.current_span()))
     }}
 
-    fn peek_char(&self) -> Option<char> {{
-        self.chars.get(self.current_pos).copied().map(|c| c as char)
+    fn is_lexable(&mut self) -> Result<bool, {lexer}Error> {{
+        Ok(self.longest_match()?.is_some())
     }}
+"#
+        )
+    }
+
+    fn write_stream(&mut self) -> Result<(), std::io::Error> {
+        let Paths {
+            terminal,
+            vec,
+            alloc,
+            ..
+        } = &self.paths;
+        let lexer = &self.options.lexer_name;
+        let errors = self.errors_getter();
+        write!(
+            self.writer,
+            r#"
+pub struct {lexer}Stream<R> {{
+    driver: {lexer}Driver<{lexer}Reader<R>>,
+}}
 
-    fn read_char(&mut self) -> Option<char> {{
-        let ch = self.peek_char();
-        if ch.is_some() {{
-            self.current_pos += 1;
+impl<R: std::io::BufRead> {lexer}Stream<R> {{
+    pub fn new(reader: R) -> Self {{
+        let reader = {lexer}Reader {{
+            reader,
+            buffer: {alloc}vec![],
+            buffer_start_pos: 0,
+            reached_eof: false,
+        }};
+        Self {{
+            driver: {lexer}Driver::new(reader),
         }}
-        ch
     }}
 
-    fn revert_char(&mut self) {{
-        self.current_pos -= 1;
+    pub fn with_tab_width(mut self, tab_width: usize) -> Self {{
+        self.driver.tab_width = tab_width.max(1);
+        self
     }}
 
-    fn skip_whitespaces(&mut self) {{
-        while let Some(c) = self.peek_char() {{
-            if c.is_whitespace() {{
-                self.read_char();
-            }} else {{
+    pub fn with_column_unit(mut self, column_unit: {lexer}ColumnUnit) -> Self {{
+        self.driver.column_unit = column_unit;
+        self
+    }}
+
+    pub fn next_token(&mut self) -> Result<{terminal}, {lexer}Error> {{
+        self.driver.next_token()
+    }}
+
+    pub fn peek_token(&mut self) -> Result<&{terminal}, {lexer}Error> {{
+        self.driver.peek_token()
+    }}
+
+    pub fn tokenize_all(&mut self) -> Result<{vec}<{terminal}>, {lexer}Error> {{
+        self.by_ref().collect()
+    }}
+{errors}
+    pub fn get_lexeme(&self, token: &{terminal}) -> Option<&str> {{
+        let span = token.span();
+        let reader = &self.driver.input;
+        let start_idx = span.start_pos().checked_sub(reader.buffer_start_pos)?;
+        let end_idx = span.end_pos() - reader.buffer_start_pos;
+        str::from_utf8(reader.buffer.get(start_idx..end_idx)?).ok()
+    }}
+}}
+
+impl<R: std::io::BufRead> Iterator for {lexer}Stream<R> {{
+    type Item = Result<{terminal}, {lexer}Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {{
+        self.driver.next()
+    }}
+}}
+
+/// Input read from a `std::io::BufRead`, of which only the current token is kept.
+struct {lexer}Reader<R> {{
+    reader: R,
+    buffer: {vec}<u8>,
+    buffer_start_pos: usize,
+    reached_eof: bool,
+}}
+
+impl<R: std::io::BufRead> {lexer}Reader<R> {{
+    fn fill_buffer(&mut self, start_pos: usize) -> Result<(), std::io::Error> {{
+        // Bytes before the current token are never backtracked to, hence they can be discarded.
+        _ = self.buffer.drain(..start_pos - self.buffer_start_pos);
+        self.buffer_start_pos = start_pos;
+        let chunk = self.reader.fill_buf()?;
+        if chunk.is_empty() {{
+            self.reached_eof = true;
+        }}
+        self.buffer.extend_from_slice(chunk);
+        let chunk_length = chunk.len();
+        self.reader.consume(chunk_length);
+        Ok(())
+    }}
+}}
+
+impl<R: std::io::BufRead> {lexer}Input for {lexer}Reader<R> {{
+    fn char_at(&mut self, pos: usize, start_pos: usize) -> Result<Option<char>, {lexer}ErrorKind> {{
+        let mut idx = pos - self.buffer_start_pos;
+        while self.buffer.len() < idx + 4 && !self.reached_eof {{
+            self.fill_buffer(start_pos).map_err({lexer}ErrorKind::Io)?;
+            idx = pos - self.buffer_start_pos;
+        }}
+        let bytes = &self.buffer[idx..self.buffer.len().min(idx + 4)];
+        let valid_bytes = match str::from_utf8(bytes) {{
+            Ok(valid_bytes) => valid_bytes,
+            Err(error) => str::from_utf8(&bytes[..error.valid_up_to()]).unwrap(),
+        }};
+        match valid_bytes.chars().next() {{
+            Some(c) => Ok(Some(c)),
+            None if bytes.is_empty() => Ok(None),
+            None => Err({lexer}ErrorKind::InvalidUtf8),
+        }}
+    }}
+
+    fn slice(&self, start_pos: usize, end_pos: usize) -> &str {{
+        let buffer_start_pos = self.buffer_start_pos;
+        let bytes = &self.buffer[start_pos - buffer_start_pos..end_pos - buffer_start_pos];
+        str::from_utf8(bytes).unwrap()
+    }}
+}}
+"#
+        )
+    }
+
+    fn write_table_next_state(&mut self) -> Result<(), std::io::Error> {
+        write!(
+            self.writer,
+            r#"
+    fn next_state(state: usize, input: char) -> Option<usize> {{
+        let next = Self::TRANSITION_TABLE[state][Self::char_class(input)];
+        (next < Self::NUM_OF_STATES).then_some(next)
+    }}
+
+    fn char_class(input: char) -> usize {{
+        if input.is_ascii() {{
+            return Self::ASCII_CLASSES[input as usize];
+        }}
+        let idx = Self::CHAR_CLASSES.partition_point(|&(_, end, _)| end < input);
+        match Self::CHAR_CLASSES.get(idx) {{
+            Some(&(start, _, class)) if start <= input => class,
+            _ => 0,
+        }}
+    }}
+"#
+        )
+    }
+
+    fn write_table_longest_match(&mut self) -> Result<(), std::io::Error> {
+        let terminal_class = &self.paths.terminal_class;
+        let lexer = &self.options.lexer_name;
+        write!(
+            self.writer,
+            r#"
+    /// Obtains the class and the end of the longest token at `current_pos`, which is restored.
+    fn longest_match(&mut self) -> Result<Option<({terminal_class}, usize)>, {lexer}Error> {{
+        let pos = self.current_pos;
+        let mut longest_match = None;
+        self.states.clear();
+        self.states.extend_from_slice({lexer}::INITIAL_STATES);
+        while let Some(c) = self.read_char()? {{
+            self.next_states.clear();
+            for &state in &self.states {{
+                if let Some(next_state) = {lexer}::next_state(state, c) {{
+                    self.next_states.push(next_state);
+                }}
+            }}
+            if self.next_states.is_empty() {{
                 break;
             }}
+            core::mem::swap(&mut self.states, &mut self.next_states);
+            let prioritized_class = self
+                .states
+                .iter()
+                .filter_map(|state| {lexer}::STATES[*state].class)
+                .min();
+            if let Some(class) = prioritized_class {{
+                longest_match = Some((class, self.current_pos));
+            }}
         }}
-        self.move_start_pos();
+        self.current_pos = pos;
+        Ok(longest_match)
     }}
+"#
+        )
+    }
 
-    fn current_span(&self) -> Span {{
-        Span::new(self.start_pos, self.current_pos)
+    fn write_direct_coded_longest_match(&mut self) -> Result<(), std::io::Error> {
+        let terminal_class = &self.paths.terminal_class;
+        let lexer = &self.options.lexer_name;
+        write!(
+            self.writer,
+            r#"
+    /// Obtains the class and the end of the longest token at `current_pos`, which is restored.
+    fn longest_match(&mut self) -> Result<Option<({terminal_class}, usize)>, {lexer}Error> {{
+        let pos = self.current_pos;
+        let mut longest_match = None;
+        let mut state = 0;
+        loop {{
+            state = match state {{
+"#
+        )?;
+        for (i, state) in self.states.iter().enumerate() {
+            Self::write_tab(&mut self.writer, 4)?;
+            let Some(token_name) = &state.accepts else {
+                if state.next.is_empty() {
+                    writeln!(self.writer, "{i} => break,")?;
+                } else {
+                    write!(self.writer, "{i} => ")?;
+                    Self::write_direct_coded_match(&mut self.writer, &state.next, 4)?;
+                    writeln!(self.writer, ",")?;
+                }
+                continue;
+            };
+            writeln!(self.writer, "{i} => {{")?;
+            Self::write_tab(&mut self.writer, 5)?;
+            writeln!(
+                self.writer,
+                "longest_match = Some(({terminal_class}::{token_name}, self.current_pos));"
+            )?;
+            Self::write_tab(&mut self.writer, 5)?;
+            if state.next.is_empty() {
+                writeln!(self.writer, "break;")?;
+            } else {
+                // The transitions are the value of the block.
+                Self::write_direct_coded_match(&mut self.writer, &state.next, 5)?;
+                writeln!(self.writer)?;
+            }
+            Self::write_tab(&mut self.writer, 4)?;
+            writeln!(self.writer, "}}")?;
+        }
+        write!(
+            self.writer,
+            r#"                _ => unreachable!(),
+            }};
+        }}
+        self.current_pos = pos;
+        Ok(longest_match)
     }}
 "#
         )
     }
 
-    fn write_tab(file: &mut File, indent: usize) -> Result<(), std::io::Error> {
+    /// Writes a `match` reading the next character and evaluating to the next state, with the
+    /// ranges leading to the same state in one arm.
+    fn write_direct_coded_match(
+        writer: &mut W,
+        next: &[(CharRange, usize)],
+        indent: usize,
+    ) -> Result<(), std::io::Error> {
+        let mut arms: Vec<(Vec<&CharRange>, usize)> = vec![];
+        for (range, next_state) in next {
+            match arms.iter_mut().find(|(_, arm_next)| arm_next == next_state) {
+                Some((ranges, _)) => ranges.push(range),
+                None => arms.push((vec![range], *next_state)),
+            }
+        }
+        writeln!(writer, "match self.read_char()? {{")?;
+        for (ranges, next_state) in arms {
+            let patterns = ranges
+                .into_iter()
+                .map(range_pattern)
+                .collect::<Vec<String>>()
+                .join(" | ");
+            Self::write_tab(writer, indent + 1)?;
+            writeln!(writer, "Some({patterns}) => {next_state},")?;
+        }
+        Self::write_tab(writer, indent + 1)?;
+        writeln!(writer, "_ => break,")?;
+        Self::write_tab(writer, indent)?;
+        write!(writer, "}}")
+    }
+
+    fn states_field(&self) -> String {
+        let vec = self.paths.vec;
+        match self.options.backend {
+            Backend::Table => format!(
+                r#"
+    // The states of the token DFAs after the characters read so far, and a buffer for the next
+    // ones, kept to avoid an allocation per character.
+    states: {vec}<usize>,
+    next_states: {vec}<usize>,"#
+            ),
+            Backend::DirectCoded => String::new(),
+        }
+    }
+
+    fn states_init(&self) -> String {
+        let vec = self.paths.vec;
+        match self.options.backend {
+            Backend::Table => format!(
+                r#"
+            states: {vec}::new(),
+            next_states: {vec}::new(),"#
+            ),
+            Backend::DirectCoded => String::new(),
+        }
+    }
+
+    fn errors_field(&self) -> String {
+        let (lexer, vec) = (&self.options.lexer_name, self.paths.vec);
+        if self.options.error_recovery {
+            format!("\n    errors: {vec}<{lexer}Error>,")
+        } else {
+            String::new()
+        }
+    }
+
+    fn errors_init(&self) -> String {
+        if self.options.error_recovery {
+            format!("\n            errors: {}::new(),", self.paths.vec)
+        } else {
+            String::new()
+        }
+    }
+
+    fn errors_getter(&self) -> String {
+        let lexer = &self.options.lexer_name;
+        if self.options.error_recovery {
+            format!(
+                r#"
+    pub fn errors(&self) -> &[{lexer}Error] {{
+        &self.driver.errors
+    }}
+"#
+            )
+        } else {
+            String::new()
+        }
+    }
+
+    fn write_tab(writer: &mut W, indent: usize) -> Result<(), std::io::Error> {
         let tab = "    ";
-        write!(file, "{}", tab.repeat(indent))
+        write!(writer, "{}", tab.repeat(indent))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Error, Options, Separators, TokenSpec};
+
+    #[test]
+    fn skip_unchanged() {
+        let output_directory = crate::test_output_directory("skip_unchanged");
+        let file_path = output_directory.join("lexer.rs");
+        let number = TokenSpec::new("Number".to_string(), "\\d\\d*".to_string());
+        let mut token_specs = vec![number];
+
+        crate::generate(&token_specs, &output_directory).unwrap();
+        let mut content = std::fs::read_to_string(&file_path).unwrap();
+        content.push_str("// untouched\n");
+        std::fs::write(&file_path, &content).unwrap();
+
+        crate::generate(&token_specs, &output_directory).unwrap();
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), content);
+
+        let plus = TokenSpec::new("Plus".to_string(), "+".to_string());
+        token_specs.push(plus);
+        crate::generate(&token_specs, &output_directory).unwrap();
+        assert_ne!(std::fs::read_to_string(&file_path).unwrap(), content);
+    }
+
+    #[test]
+    fn standalone() {
+        let token_specs = vec![
+            TokenSpec::new("Number".to_string(), "\\d\\d*".to_string()),
+            TokenSpec::new("Name".to_string(), "\\w\\w*".to_string()),
+            TokenSpec::new("Number".to_string(), "\\d*x\\d\\d*".to_string()),
+        ];
+        let content = Options::new()
+            .standalone(true)
+            .generate_to_string(&token_specs)
+            .unwrap();
+        assert!(content.contains("pub enum TerminalClass {\n    Number,\n    Name,\n    End,\n}"));
+        assert!(!content.contains("crate::symbol"));
+    }
+
+    #[test]
+    fn symbol_names() {
+        let token_specs = vec![TokenSpec::new("Number".to_string(), "\\d\\d*".to_string())];
+        let content = Options::new()
+            .symbol_module("crate::frontend::syntax")
+            .terminal_name("Token")
+            .terminal_class_name("TokenKind")
+            .generate_to_string(&token_specs)
+            .unwrap();
+        assert!(content.contains("crate::frontend::syntax::Token::new("));
+        assert!(content.contains("crate::frontend::syntax::TokenKind::End"));
+        assert!(content.contains("span: &crate::frontend::syntax::Span)"));
+        assert!(!content.contains("\nuse "));
+    }
+
+    #[test]
+    fn multiple_lexers() {
+        let output_directory = crate::test_output_directory("multiple_lexers");
+        let token_specs = vec![TokenSpec::new("Number".to_string(), "\\d\\d*".to_string())];
+        crate::generate(&token_specs, &output_directory).unwrap();
+        Options::new()
+            .file_name("query_lexer.rs")
+            .lexer_name("QueryLexer")
+            .generate(&token_specs, &output_directory)
+            .unwrap();
+        let content = std::fs::read_to_string(output_directory.join("query_lexer.rs")).unwrap();
+        assert!(content.contains("pub struct QueryLexer<'src> {"));
+        assert!(content.contains("struct QueryLexerState {"));
+        let content = std::fs::read_to_string(output_directory.join("lexer.rs")).unwrap();
+        assert!(content.contains("pub struct Lexer<'src> {"));
+    }
+
+    #[test]
+    fn reserved_token_name() {
+        let token_specs = vec![TokenSpec::new("Error".to_string(), "!".to_string())];
+        assert!(Options::new().generate_to_string(&token_specs).is_ok());
+        assert!(matches!(
+            Options::new()
+                .error_recovery(true)
+                .generate_to_string(&token_specs),
+            Err(Error::ReservedTokenName { token }) if token == "Error"
+        ));
+    }
+
+    #[test]
+    fn reserved_end_token_name() {
+        let token_specs = vec![
+            TokenSpec::new("End".to_string(), "end".to_string()),
+            TokenSpec::new("Number".to_string(), "\\d\\d*".to_string()),
+        ];
+        for options in [Options::new(), Options::new().standalone(true)] {
+            assert!(matches!(
+                options.generate_to_string(&token_specs),
+                Err(Error::ReservedTokenName { token }) if token == "End"
+            ));
+        }
+    }
+
+    #[test]
+    fn separators() {
+        let token_specs = vec![TokenSpec::new("Number".to_string(), "\\d\\d*".to_string())];
+        let content = Options::new()
+            .separators(Separators::None)
+            .generate_to_string(&token_specs)
+            .unwrap();
+        assert!(content.contains("fn is_separator(_: char) -> bool {\n        false\n    }"));
+
+        let content = Options::new()
+            .separators(Separators::Class("\\d| |_".to_string()))
+            .generate_to_string(&token_specs)
+            .unwrap();
+        assert!(content.contains("matches!(c, ' ' | '0'..='9' | '_')"));
+
+        let result = Options::new()
+            .separators(Separators::WhitespaceExcept("\\n*".to_string()))
+            .generate_to_string(&token_specs);
+        assert!(matches!(result, Err(Error::InvalidSeparators { .. })));
+    }
+
+    #[test]
+    fn generate_to_string() {
+        let token_specs = vec![TokenSpec::new("Number".to_string(), "\\d\\d*".to_string())];
+        let mut content = vec![];
+        crate::generate_to_writer(&token_specs, &mut content).unwrap();
+        let content = String::from_utf8(content).unwrap();
+        assert!(content.starts_with("// @generated by JLEK"));
+        assert_eq!(crate::generate_to_string(&token_specs).unwrap(), content);
     }
 }
@@ -0,0 +1,100 @@
+use std::{fmt::Display, path::PathBuf};
+
+/// An error that occurred while generating a lexer.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Reading a spec file or writing the generated lexer failed.
+    Io(std::io::Error),
+    /// Line `line` of the spec file at `path` is not a valid token specification.
+    InvalidSpecFile {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    /// No output directory was given to [Builder](crate::Builder) and `$OUT_DIR` is not set.
+    MissingOutputDirectory,
+    /// The pattern of the token specification named `token` is not a valid regular expression, or
+    /// matches the empty string.
+    InvalidPattern { token: String, message: String },
+    /// The DFA of the token specification named `token` reached `states` states, exceeding the
+    /// limit set with [Options::max_states_per_token](crate::Options::max_states_per_token).
+    TooManyTokenStates {
+        token: String,
+        states: usize,
+        limit: usize,
+    },
+    /// Adding the DFA of the token specification named `token` brought the total number of states
+    /// to `states`, exceeding the limit set with
+    /// [Options::max_states](crate::Options::max_states). With
+    /// [Backend::DirectCoded](crate::Backend::DirectCoded), `token` may also be the last token
+    /// specification when the DFA combining all of them has too many states.
+    TooManyStates {
+        token: String,
+        states: usize,
+        limit: usize,
+    },
+    /// The token specification named `token` takes the name of a token class emitted by the lexer
+    /// itself, i.e. `End`, or `Error` with
+    /// [Options::error_recovery](crate::Options::error_recovery).
+    ReservedTokenName { token: String },
+    /// The pattern of [Options::separators](crate::Options::separators) is not a valid character
+    /// class.
+    InvalidSeparators { message: String },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(error) => write!(f, "I/O error: {error}"),
+            Error::InvalidSpecFile {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{line}: {message}", path.display()),
+            Error::MissingOutputDirectory => {
+                write!(f, "no output directory given and `OUT_DIR` is not set")
+            }
+            Error::InvalidPattern { token, message } => {
+                write!(f, "invalid pattern for token `{token}`: {message}")
+            }
+            Error::TooManyTokenStates {
+                token,
+                states,
+                limit,
+            } => write!(
+                f,
+                "DFA for token `{token}` reached {states} states, exceeding the limit of {limit}"
+            ),
+            Error::TooManyStates {
+                token,
+                states,
+                limit,
+            } => write!(
+                f,
+                "lexer reached {states} states at token `{token}`, exceeding the limit of {limit}"
+            ),
+            Error::ReservedTokenName { token } => {
+                write!(f, "token name `{token}` is reserved by the lexer")
+            }
+            Error::InvalidSeparators { message } => {
+                write!(f, "invalid separators class: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error)
+    }
+}
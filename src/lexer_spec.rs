@@ -1,304 +1,464 @@
-use std::{
-    collections::{HashMap, HashSet},
-    rc::Rc,
-};
+mod thompson;
+
+use std::collections::{BTreeMap, HashMap};
 
 use crate::{
-    TokenSpec,
-    regex_parser::{self, RegexNode, RegexTerminal},
+    Construction, Error, Options, TokenSpec,
+    regex_parser::{self, CharRange, NodeId, Regex, RegexNode},
 };
 
 pub struct LexerSpec<'a> {
     pub token_specs: &'a Vec<TokenSpec>,
     pub states: Vec<State>,
     pub initial_states: Vec<usize>,
+    /// Equivalence class of each character range, sorted by range. Characters which behave
+    /// identically in every state share a class. Characters outside of these ranges belong to
+    /// class 0, which has no transitions.
+    pub char_classes: Vec<(CharRange, usize)>,
+    pub num_of_classes: usize,
+    /// Next state of each state on each character class.
+    pub transition_table: Vec<Vec<Option<usize>>>,
 }
 
 #[derive(Debug)]
 pub struct State {
     pub accepts: Option<String>,
-    pub next: HashMap<char, usize>,
+    /// Transitions on disjoint character ranges, sorted by range.
+    pub next: Vec<(CharRange, usize)>,
 }
 
 #[derive(Debug)]
 struct DfaState {
-    terminals: HashSet<RegexTerminal>,
-    next: HashMap<char, usize>,
+    accepting: bool,
+    next: Vec<(CharRange, usize)>,
 }
 
-#[derive(Default)]
+/// Per node and per position tables for the followpos construction.
+///
+/// Unlike nullable and followpos, firstpos and lastpos are not tabulated for every node. They are
+/// gathered on demand instead, which keeps long alternations (e.g. keywords) linear, since the
+/// firstpos of every prefix of an alternation is never materialized.
 struct Cache {
-    first_pos_table: HashMap<Rc<RegexNode>, HashSet<RegexTerminal>>,
-    last_pos_table: HashMap<Rc<RegexNode>, HashSet<RegexTerminal>>,
-    follow_pos_table: HashMap<RegexTerminal, HashSet<RegexTerminal>>,
-    nullable_table: HashMap<Rc<RegexNode>, bool>,
+    nullable_table: Vec<bool>,
+    follow_pos_table: Vec<Vec<usize>>,
 }
 
 impl<'a> LexerSpec<'a> {
-    pub fn new(token_specs: &'a Vec<TokenSpec>) -> Self {
-        Self {
+    pub fn new(token_specs: &'a Vec<TokenSpec>, options: &Options) -> Result<Self, Error> {
+        let lexer_spec = Self::construct(token_specs, options)?;
+        // A token matching the empty string would be lexed forever without consuming any input.
+        for (token_spec, &initial_state) in token_specs.iter().zip(&lexer_spec.initial_states) {
+            if lexer_spec.states[initial_state].accepts.is_some() {
+                return Err(Error::InvalidPattern {
+                    token: token_spec.name.clone(),
+                    message: "Pattern matches the empty string".to_string(),
+                });
+            }
+        }
+        Ok(lexer_spec)
+    }
+
+    fn construct(token_specs: &'a Vec<TokenSpec>, options: &Options) -> Result<Self, Error> {
+        let lexer_spec = Self {
             token_specs,
             states: vec![],
             initial_states: vec![],
+            char_classes: vec![],
+            num_of_classes: 1,
+            transition_table: vec![],
         }
-        .fill_states()
+        .fill_states(options)?
+        .fill_char_classes();
+        Ok(lexer_spec)
     }
 
-    fn fill_states(mut self) -> Self {
+    /// Obtains a single DFA running the DFAs of all tokens at once, whose state 0 is initial. Each
+    /// of its states is a set of token DFA states, and accepts the highest priority token among
+    /// them, i.e. the one whose name is specified first.
+    pub fn combined_states(&self, options: &Options) -> Result<Vec<State>, Error> {
+        let priority = |name: &String| {
+            self.token_specs
+                .iter()
+                .position(|token_spec| &token_spec.name == name)
+        };
+        // Each token DFA contributes at most one state, hence the sets are sorted.
+        let mut state_sets = vec![self.initial_states.clone()];
+        let mut state_indices = HashMap::from([(self.initial_states.clone(), 0)]);
+        let mut states = vec![];
+        while let Some(state_set) = state_sets.get(states.len()).cloned() {
+            let accepts = state_set
+                .iter()
+                .filter_map(|&state| self.states[state].accepts.as_ref())
+                .min_by_key(|name| priority(name))
+                .cloned();
+            let ranges = state_set
+                .iter()
+                .flat_map(|&state| self.states[state].next.iter().map(|&(range, _)| range))
+                .collect::<Vec<CharRange>>();
+            let mut combined_state = DfaState {
+                accepting: false,
+                next: vec![],
+            };
+            for range in CharRange::partition(&ranges) {
+                let next_set = state_set
+                    .iter()
+                    .filter_map(|&state| self.states[state].next_state(range.start))
+                    .collect::<Vec<usize>>();
+                if next_set.is_empty() {
+                    continue;
+                }
+                let next = *state_indices
+                    .entry(next_set)
+                    .or_insert_with_key(|next_set| {
+                        state_sets.push(next_set.clone());
+                        state_sets.len() - 1
+                    });
+                combined_state.add_transition(range, next);
+            }
+            if state_sets.len() > options.max_states {
+                return Err(Error::TooManyStates {
+                    token: self.token_specs.last().unwrap().name.clone(),
+                    states: state_sets.len(),
+                    limit: options.max_states,
+                });
+            }
+            states.push(State {
+                accepts,
+                next: combined_state.next,
+            });
+        }
+        Ok(states)
+    }
+
+    fn fill_states(mut self, options: &Options) -> Result<Self, Error> {
         for token_spec in self.token_specs {
-            let dfa = Self::create_dfa(&token_spec.pattern);
+            let max_states = options.max_states_per_token;
+            let dfa = match options.construction {
+                Construction::FollowPos => Self::create_dfa(token_spec, max_states)?,
+                Construction::Thompson => thompson::create_dfa(token_spec, max_states)?,
+            };
+            if self.states.len() + dfa.len() > options.max_states {
+                return Err(Error::TooManyStates {
+                    token: token_spec.name.clone(),
+                    states: self.states.len() + dfa.len(),
+                    limit: options.max_states,
+                });
+            }
 
             let dfa_root_idx = self.states.len();
             self.initial_states.push(dfa_root_idx);
 
             for dfa_state in dfa {
-                let accepts = dfa_state.is_accepting().then_some(token_spec.name.clone());
+                let accepts = dfa_state.accepting.then_some(token_spec.name.clone());
                 let next = dfa_state
                     .next
                     .iter()
-                    .map(|(&ch, next)| (ch, next + dfa_root_idx))
+                    .map(|&(range, next)| (range, next + dfa_root_idx))
                     .collect();
                 let state = State { accepts, next };
                 self.states.push(state);
             }
         }
+        Ok(self)
+    }
+
+    fn fill_char_classes(mut self) -> Self {
+        let ranges = self
+            .states
+            .iter()
+            .flat_map(|s| s.next.iter().map(|&(range, _)| range))
+            .collect::<Vec<CharRange>>();
+
+        let mut classes = HashMap::new();
+        for range in CharRange::partition(&ranges) {
+            let class_next = self
+                .states
+                .iter()
+                .map(|s| s.next_state(range.start))
+                .collect::<Vec<Option<usize>>>();
+            let new_class = classes.len() + 1;
+            let class = *classes.entry(class_next).or_insert(new_class);
+            match self.char_classes.last_mut() {
+                Some((last_range, last_class))
+                    if *last_class == class
+                        && char::from_u32(last_range.end as u32 + 1) == Some(range.start) =>
+                {
+                    last_range.end = range.end
+                }
+                _ => self.char_classes.push((range, class)),
+            }
+        }
+        self.num_of_classes = classes.len() + 1;
+
+        self.transition_table = vec![vec![None; self.num_of_classes]; self.states.len()];
+        // Each class fills its own column, hence the table doesn't depend on the iteration order.
+        for (class_next, class) in classes {
+            for (state, next) in class_next.into_iter().enumerate() {
+                self.transition_table[state][class] = next;
+            }
+        }
         self
     }
 
-    fn create_dfa(pattern: &str) -> Vec<DfaState> {
-        let (regex_root, alphabet) = regex_parser::parse_regex(pattern).unwrap();
-        let cache = Cache::new(&regex_root);
+    fn create_dfa(token_spec: &TokenSpec, max_states: usize) -> Result<Vec<DfaState>, Error> {
+        let regex = Self::parse_regex(token_spec)?;
+        let cache = Cache::new(&regex);
 
-        let first_state = DfaState::new(cache.first_pos(&regex_root).clone());
-        let mut states = vec![first_state];
-        let mut visited_states = 0;
+        let mut first_pos = cache.first_pos(&regex, regex.root());
+        first_pos.sort_unstable();
+        let mut state_indices = HashMap::from([(first_pos.clone(), 0)]);
+        let mut position_sets = vec![first_pos];
+        let mut states = vec![];
 
-        while visited_states < states.len() {
-            for ch in &alphabet {
-                let mut follow_pos_union = HashSet::new();
-                for terminal in states[visited_states]
-                    .terminals
+        while states.len() < position_sets.len() {
+            let positions = &position_sets[states.len()];
+            let mut state = DfaState {
+                accepting: positions
                     .iter()
-                    .filter(|&t| t.ch == *ch)
-                {
-                    if let Some(follow_pos) = cache.follow_pos(terminal) {
-                        follow_pos_union = &follow_pos_union | follow_pos;
-                    }
-                }
-                if let Some(idx) = states.iter().position(|s| follow_pos_union == s.terminals) {
-                    states[visited_states].next.insert(*ch, idx);
-                } else if !follow_pos_union.is_empty() {
-                    let new_state = DfaState::new(follow_pos_union);
-                    let idx = states.len();
-                    states[visited_states].next.insert(*ch, idx);
-                    states.push(new_state);
+                    .any(|&pos| regex.terminals[pos].range.is_none()),
+                next: vec![],
+            };
+            let mut follow_pos_unions = BTreeMap::new();
+            for &pos in positions {
+                let Some(range) = regex.terminals[pos].range else {
+                    continue;
+                };
+                let first = regex.alphabet.partition_point(|r| r.end < range.start);
+                let last = regex.alphabet.partition_point(|r| r.start <= range.end);
+                for idx in first..last {
+                    let follow_pos_union: &mut Vec<usize> =
+                        follow_pos_unions.entry(idx).or_default();
+                    follow_pos_union.extend(cache.follow_pos(pos));
                 }
             }
-            visited_states += 1;
+            for (idx, mut follow_pos_union) in follow_pos_unions {
+                follow_pos_union.sort_unstable();
+                follow_pos_union.dedup();
+                let next = *state_indices
+                    .entry(follow_pos_union.clone())
+                    .or_insert_with(|| {
+                        position_sets.push(follow_pos_union);
+                        position_sets.len() - 1
+                    });
+                state.add_transition(regex.alphabet[idx], next);
+            }
+            Self::check_num_of_states(token_spec, position_sets.len(), max_states)?;
+            states.push(state);
+        }
+        Ok(states)
+    }
+
+    fn parse_regex(token_spec: &TokenSpec) -> Result<Regex, Error> {
+        regex_parser::parse_regex(&token_spec.pattern).map_err(|message| Error::InvalidPattern {
+            token: token_spec.name.clone(),
+            message,
+        })
+    }
+
+    fn check_num_of_states(
+        token_spec: &TokenSpec,
+        num_of_states: usize,
+        max_states: usize,
+    ) -> Result<(), Error> {
+        if num_of_states > max_states {
+            return Err(Error::TooManyTokenStates {
+                token: token_spec.name.clone(),
+                states: num_of_states,
+                limit: max_states,
+            });
         }
-        states
+        Ok(())
     }
 }
 
-impl DfaState {
-    fn new(terminals: HashSet<RegexTerminal>) -> Self {
-        Self {
-            terminals,
-            next: HashMap::new(),
+impl State {
+    pub fn next_state(&self, ch: char) -> Option<usize> {
+        let idx = self.next.partition_point(|(range, _)| range.end < ch);
+        match self.next.get(idx) {
+            Some(&(range, next)) if range.start <= ch => Some(next),
+            _ => None,
         }
     }
+}
 
-    fn is_accepting(&self) -> bool {
-        self.terminals.iter().any(|t| t.ch == '\0')
+impl DfaState {
+    /// Adds a transition on `range`, which must come after the ranges of existing transitions.
+    /// Adjacent ranges leading to the same state are merged.
+    fn add_transition(&mut self, range: CharRange, next: usize) {
+        if let Some((last_range, last_next)) = self.next.last_mut()
+            && *last_next == next
+            && char::from_u32(last_range.end as u32 + 1) == Some(range.start)
+        {
+            last_range.end = range.end;
+        } else {
+            self.next.push((range, next));
+        }
     }
 }
 
 impl Cache {
-    fn new(root_node: &Rc<RegexNode>) -> Self {
-        let mut cache = Self::default();
-        cache.calculate_nullable(root_node);
-        cache.calculate_first_pos(root_node);
-        cache.calculate_last_pos(root_node);
-        cache.calculate_follow_pos(root_node);
+    fn new(regex: &Regex) -> Self {
+        let mut cache = Self {
+            nullable_table: vec![false; regex.nodes.len()],
+            follow_pos_table: vec![vec![]; regex.terminals.len()],
+        };
+        cache.calculate_nullable(regex);
+        cache.calculate_follow_pos(regex);
         cache
     }
 
-    fn calculate_nullable(&mut self, node: &Rc<RegexNode>) {
-        match &**node {
-            RegexNode::Cat(left, right) => {
-                self.calculate_nullable(left);
-                self.calculate_nullable(right);
-                let left_nullable = self.nullable(left);
-                let right_nullable = self.nullable(right);
-                let nullable = left_nullable && right_nullable;
-                _ = self.nullable_table.insert(node.clone(), nullable);
-            }
-            RegexNode::Or(left, right) => {
-                self.calculate_nullable(left);
-                self.calculate_nullable(right);
-                let left_nullable = self.nullable(left);
-                let right_nullable = self.nullable(right);
-                let nullable = left_nullable || right_nullable;
-                _ = self.nullable_table.insert(node.clone(), nullable);
-            }
-            RegexNode::Parenthesized(child) => {
-                self.calculate_nullable(child);
-                let nullable = self.nullable(child);
-                _ = self.nullable_table.insert(node.clone(), nullable);
-            }
-            RegexNode::Kleene(child) => {
-                self.calculate_nullable(child);
-                _ = self.nullable_table.insert(node.clone(), true);
-            }
-            RegexNode::Terminal(_) => _ = self.nullable_table.insert(node.clone(), false),
-        };
+    fn calculate_nullable(&mut self, regex: &Regex) {
+        // Children are placed before their parent, hence visiting nodes in order is bottom-up.
+        for (id, node) in regex.nodes.iter().enumerate() {
+            self.nullable_table[id] = match *node {
+                RegexNode::Cat(left, right) => self.nullable(left) && self.nullable(right),
+                RegexNode::Or(left, right) => self.nullable(left) || self.nullable(right),
+                RegexNode::Parenthesized(child) => self.nullable(child),
+                RegexNode::Kleene(_) => true,
+                RegexNode::Terminal(_) => false,
+            };
+        }
     }
 
-    fn calculate_first_pos(&mut self, node: &Rc<RegexNode>) {
-        match &**node {
-            RegexNode::Cat(left, right) => {
-                self.calculate_first_pos(left);
-                self.calculate_first_pos(right);
-                let left_first_pos = self.first_pos(left);
-                let right_first_pos = self.first_pos(right);
-                let left_nullable = self.nullable(left);
-                let first_pos = if left_nullable {
-                    left_first_pos.union(right_first_pos).cloned().collect()
-                } else {
-                    left_first_pos.clone()
-                };
-                _ = self.first_pos_table.insert(node.clone(), first_pos);
-            }
-            RegexNode::Or(left, right) => {
-                self.calculate_first_pos(left);
-                self.calculate_first_pos(right);
-                let left_first_pos = self.first_pos(left);
-                let right_first_pos = self.first_pos(right);
-                let first_pos = left_first_pos.union(right_first_pos).cloned().collect();
-                _ = self.first_pos_table.insert(node.clone(), first_pos);
-            }
-            RegexNode::Parenthesized(child) | RegexNode::Kleene(child) => {
-                self.calculate_first_pos(child);
-                let first_pos = self.first_pos(child).clone();
-                _ = self.first_pos_table.insert(node.clone(), first_pos);
-            }
-            RegexNode::Terminal(t) => {
-                let first_pos = HashSet::from([t.clone()]);
-                _ = self.first_pos_table.insert(node.clone(), first_pos);
+    fn calculate_follow_pos(&mut self, regex: &Regex) {
+        for node in &regex.nodes {
+            let (last_pos, first_pos) = match *node {
+                RegexNode::Cat(left, right) => {
+                    (self.last_pos(regex, left), self.first_pos(regex, right))
+                }
+                RegexNode::Kleene(child) => {
+                    (self.last_pos(regex, child), self.first_pos(regex, child))
+                }
+                _ => continue,
+            };
+            for pos in last_pos {
+                self.follow_pos_table[pos].extend(&first_pos);
             }
         }
+        for follow_pos in &mut self.follow_pos_table {
+            follow_pos.sort_unstable();
+            follow_pos.dedup();
+        }
     }
 
-    fn calculate_last_pos(&mut self, node: &Rc<RegexNode>) {
-        match &**node {
-            RegexNode::Cat(left, right) => {
-                self.calculate_last_pos(left);
-                self.calculate_last_pos(right);
-                let left_last_pos = self.last_pos(left);
-                let right_last_pos = self.last_pos(right);
-                let right_nullable = self.nullable(right);
-                let last_pos = if right_nullable {
-                    right_last_pos.union(left_last_pos).cloned().collect()
-                } else {
-                    right_last_pos.clone()
-                };
-                _ = self.last_pos_table.insert(node.clone(), last_pos);
-            }
-            RegexNode::Or(left, right) => {
-                self.calculate_last_pos(left);
-                self.calculate_last_pos(right);
-                let left_last_pos = self.last_pos(left);
-                let right_last_pos = self.last_pos(right);
-                let last_pos = left_last_pos.union(right_last_pos).cloned().collect();
-                _ = self.last_pos_table.insert(node.clone(), last_pos);
-            }
-            RegexNode::Parenthesized(child) | RegexNode::Kleene(child) => {
-                self.calculate_last_pos(child);
-                let last_pos = self.last_pos(child).clone();
-                _ = self.last_pos_table.insert(node.clone(), last_pos);
-            }
-            RegexNode::Terminal(t) => {
-                let last_pos = HashSet::from([t.clone()]);
-                _ = self.last_pos_table.insert(node.clone(), last_pos);
-            }
-        }
+    fn nullable(&self, node: NodeId) -> bool {
+        self.nullable_table[node]
     }
 
-    fn calculate_follow_pos(&mut self, node: &Rc<RegexNode>) {
-        match &**node {
-            RegexNode::Cat(left, right) => {
-                let left_last_pos = self.last_pos_table.get(left).unwrap();
-                let right_first_pos = self.first_pos_table.get(right).unwrap();
-                for terminal in left_last_pos {
-                    if !self.follow_pos_table.contains_key(terminal) {
-                        self.follow_pos_table
-                            .insert(terminal.clone(), HashSet::new());
-                    }
-                    for follow in right_first_pos {
-                        self.follow_pos_table
-                            .get_mut(terminal)
-                            .unwrap()
-                            .insert(follow.clone());
+    fn first_pos(&self, regex: &Regex, node: NodeId) -> Vec<usize> {
+        let mut first_pos = vec![];
+        let mut pending = vec![node];
+        while let Some(node) = pending.pop() {
+            match regex.nodes[node] {
+                RegexNode::Cat(left, right) => {
+                    pending.push(left);
+                    if self.nullable(left) {
+                        pending.push(right);
                     }
                 }
-                self.calculate_follow_pos(left);
-                self.calculate_follow_pos(right);
+                RegexNode::Or(left, right) => pending.extend([left, right]),
+                RegexNode::Parenthesized(child) | RegexNode::Kleene(child) => pending.push(child),
+                RegexNode::Terminal(terminal) => first_pos.push(terminal.pos),
             }
-            RegexNode::Kleene(node) => {
-                let last_pos = self.last_pos_table.get(node).unwrap();
-                let first_pos = self.first_pos_table.get(node).unwrap();
-                for terminal in last_pos {
-                    if !self.follow_pos_table.contains_key(terminal) {
-                        self.follow_pos_table
-                            .insert(terminal.clone(), HashSet::new());
-                    }
-                    for follow in first_pos {
-                        self.follow_pos_table
-                            .get_mut(terminal)
-                            .unwrap()
-                            .insert(follow.clone());
+        }
+        first_pos
+    }
+
+    fn last_pos(&self, regex: &Regex, node: NodeId) -> Vec<usize> {
+        let mut last_pos = vec![];
+        let mut pending = vec![node];
+        while let Some(node) = pending.pop() {
+            match regex.nodes[node] {
+                RegexNode::Cat(left, right) => {
+                    pending.push(right);
+                    if self.nullable(right) {
+                        pending.push(left);
                     }
                 }
-                self.calculate_follow_pos(node);
+                RegexNode::Or(left, right) => pending.extend([left, right]),
+                RegexNode::Parenthesized(child) | RegexNode::Kleene(child) => pending.push(child),
+                RegexNode::Terminal(terminal) => last_pos.push(terminal.pos),
             }
-            RegexNode::Parenthesized(node) => self.calculate_follow_pos(node),
-            RegexNode::Or(left, right) => {
-                self.calculate_follow_pos(left);
-                self.calculate_nullable(right);
-            }
-            RegexNode::Terminal(_) => (),
         }
+        last_pos
     }
 
-    fn nullable(&self, node: &Rc<RegexNode>) -> bool {
-        *self.nullable_table.get(node).unwrap()
+    fn follow_pos(&self, pos: usize) -> &[usize] {
+        &self.follow_pos_table[pos]
     }
+}
 
-    fn first_pos(&self, node: &Rc<RegexNode>) -> &HashSet<RegexTerminal> {
-        self.first_pos_table.get(node).unwrap()
-    }
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
 
-    fn last_pos(&self, node: &Rc<RegexNode>) -> &HashSet<RegexTerminal> {
-        self.last_pos_table.get(node).unwrap()
-    }
+    use crate::{
+        Construction, Error, Options, TokenSpec, lexer_spec::LexerSpec, regex_parser::CharRange,
+    };
 
-    fn follow_pos(&self, node: &RegexTerminal) -> Option<&HashSet<RegexTerminal>> {
-        self.follow_pos_table.get(node)
+    /// Checks that [Construction::FollowPos] and [Construction::Thompson] construct equivalent
+    /// DFAs for `token_specs`, i.e. DFAs accepting the same lexemes for each token. Panics
+    /// otherwise, including when only one of the constructions succeeds.
+    ///
+    /// The state limits are lifted, as the constructions may require a different number of states.
+    fn assert_constructions_equivalent(token_specs: &Vec<TokenSpec>) {
+        let options = Options::new()
+            .max_states_per_token(usize::MAX)
+            .max_states(usize::MAX);
+        let follow_pos = LexerSpec::construct(
+            token_specs,
+            &options.clone().construction(Construction::FollowPos),
+        );
+        let thompson =
+            LexerSpec::construct(token_specs, &options.construction(Construction::Thompson));
+        match (follow_pos, thompson) {
+            (Ok(follow_pos), Ok(thompson)) => assert_equivalent(&follow_pos, &thompson),
+            (Err(_), Err(_)) => {}
+            (Ok(_), Err(error)) => panic!("only the followpos construction succeeded: {error}"),
+            (Err(error), Ok(_)) => panic!("only the Thompson construction succeeded: {error}"),
+        }
     }
-}
 
-impl RegexNode {
-    pub fn terminal(ch: char, pos: usize) -> Self {
-        let terminal = RegexTerminal { ch, pos };
-        Self::Terminal(terminal)
+    fn assert_equivalent(lexer_spec: &LexerSpec, other: &LexerSpec) {
+        assert_eq!(
+            lexer_spec.initial_states.len(),
+            other.initial_states.len(),
+            "numbers of token DFAs differ"
+        );
+        for (&initial, &other_initial) in
+            lexer_spec.initial_states.iter().zip(&other.initial_states)
+        {
+            let mut visited = HashSet::from([(initial, other_initial)]);
+            let mut pending = vec![(initial, other_initial)];
+            while let Some((state, other_state)) = pending.pop() {
+                let (state, other_state) = (&lexer_spec.states[state], &other.states[other_state]);
+                assert_eq!(state.accepts, other_state.accepts);
+                let ranges = state
+                    .next
+                    .iter()
+                    .chain(&other_state.next)
+                    .map(|&(range, _)| range)
+                    .collect::<Vec<CharRange>>();
+                for range in CharRange::partition(&ranges) {
+                    match (
+                        state.next_state(range.start),
+                        other_state.next_state(range.start),
+                    ) {
+                        (Some(next), Some(other_next)) => {
+                            if visited.insert((next, other_next)) {
+                                pending.push((next, other_next));
+                            }
+                        }
+                        (next, other_next) => assert_eq!(
+                            next.is_some(),
+                            other_next.is_some(),
+                            "transitions on {range:?} differ"
+                        ),
+                    }
+                }
+            }
+        }
     }
-}
-
-#[cfg(test)]
-mod test {
-    use std::collections::HashMap;
-
-    use crate::{TokenSpec, lexer_spec::LexerSpec};
 
     #[test]
     fn number() {
@@ -307,39 +467,183 @@ mod test {
             pattern: "\\d\\d*".to_string(),
         };
         let token_specs = vec![number];
-        let lexer_spec = LexerSpec::new(&token_specs);
+        let lexer_spec = LexerSpec::new(&token_specs, &Options::default()).unwrap();
         assert_eq!(&lexer_spec.initial_states, &vec![0]);
         assert_eq!(&lexer_spec.states[0].accepts, &None);
         assert_eq!(
             &lexer_spec.states[0].next,
-            &HashMap::from([
-                ('0', 1),
-                ('1', 1),
-                ('2', 1),
-                ('3', 1),
-                ('4', 1),
-                ('5', 1),
-                ('6', 1),
-                ('7', 1),
-                ('8', 1),
-                ('9', 1),
-            ])
+            &vec![(CharRange::new('0', '9'), 1)]
         );
         assert_eq!(&lexer_spec.states[1].accepts, &Some("Number".to_string()));
         assert_eq!(
             &lexer_spec.states[1].next,
-            &HashMap::from([
-                ('0', 1),
-                ('1', 1),
-                ('2', 1),
-                ('3', 1),
-                ('4', 1),
-                ('5', 1),
-                ('6', 1),
-                ('7', 1),
-                ('8', 1),
-                ('9', 1),
-            ])
+            &vec![(CharRange::new('0', '9'), 1)]
+        );
+    }
+
+    #[test]
+    fn overlapping_ranges() {
+        let identifier = TokenSpec {
+            name: "Identifier".to_string(),
+            pattern: "e\\w*".to_string(),
+        };
+        let token_specs = vec![identifier];
+        let lexer_spec = LexerSpec::new(&token_specs, &Options::default()).unwrap();
+        assert_eq!(
+            &lexer_spec.states[0].next,
+            &vec![(CharRange::new('e', 'e'), 1)]
+        );
+        assert_eq!(
+            &lexer_spec.states[1].next,
+            &vec![(CharRange::new('a', 'z'), 1)]
+        );
+    }
+
+    #[test]
+    fn char_classes() {
+        let identifier = TokenSpec {
+            name: "Identifier".to_string(),
+            pattern: "\\w\\w*".to_string(),
+        };
+        let exponent = TokenSpec {
+            name: "Exponent".to_string(),
+            pattern: "e\\d".to_string(),
+        };
+        let token_specs = vec![identifier, exponent];
+        let lexer_spec = LexerSpec::new(&token_specs, &Options::default()).unwrap();
+        assert_eq!(
+            &lexer_spec.char_classes,
+            &vec![
+                (CharRange::new('0', '9'), 1),
+                (CharRange::new('a', 'd'), 2),
+                (CharRange::new('e', 'e'), 3),
+                (CharRange::new('f', 'z'), 2),
+            ]
         );
+        assert_eq!(lexer_spec.num_of_classes, 4);
+        assert_eq!(
+            &lexer_spec.transition_table[0],
+            &vec![None, None, Some(1), Some(1)]
+        );
+        assert_eq!(
+            &lexer_spec.transition_table[2],
+            &vec![None, None, None, Some(3)]
+        );
+    }
+
+    #[test]
+    fn constructions() {
+        let patterns = [
+            "-|->",
+            "a|bc",
+            "(ab|a)*b",
+            "((a|b)*c)*",
+            "'(\\w|\\d)*'",
+            "x(y*|z)*",
+            "(a|b)|(c|d)*",
+            "ab|(c|d|e)|f*",
+        ];
+        let token_specs = patterns
+            .iter()
+            .enumerate()
+            .map(|(i, pattern)| TokenSpec::new(format!("Token{i}"), pattern.to_string()))
+            .collect::<Vec<TokenSpec>>();
+        assert_constructions_equivalent(&token_specs);
+    }
+
+    #[test]
+    fn fixture_constructions() {
+        let token_specs: [(&str, &str); 8] = include!("../tests/common/token_specs.rs");
+        let token_specs = token_specs
+            .into_iter()
+            .map(|(name, pattern)| TokenSpec::new(name.to_string(), pattern.to_string()))
+            .collect::<Vec<TokenSpec>>();
+        assert_constructions_equivalent(&token_specs);
+    }
+
+    #[test]
+    fn empty_lexeme() {
+        for pattern in ["a*", "(b|a*)", "a*(b*)"] {
+            let token_specs = vec![
+                TokenSpec::new("B".to_string(), "b".to_string()),
+                TokenSpec::new("As".to_string(), pattern.to_string()),
+            ];
+            for construction in [Construction::FollowPos, Construction::Thompson] {
+                let options = Options::new().construction(construction);
+                match LexerSpec::new(&token_specs, &options) {
+                    Err(Error::InvalidPattern { token, .. }) => assert_eq!(token, "As"),
+                    _ => panic!("expected {pattern:?} to be rejected"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn state_budget() {
+        let token_specs = vec![
+            TokenSpec::new("Small".to_string(), "ab".to_string()),
+            TokenSpec::new(
+                "Exploding".to_string(),
+                "(a|b)*a(a|b)(a|b)(a|b)(a|b)".to_string(),
+            ),
+        ];
+        for construction in [Construction::FollowPos, Construction::Thompson] {
+            let options = Options::new()
+                .construction(construction)
+                .max_states_per_token(16);
+            match LexerSpec::new(&token_specs, &options) {
+                Err(Error::TooManyTokenStates {
+                    token,
+                    states,
+                    limit,
+                }) => {
+                    assert_eq!(token, "Exploding");
+                    assert!(states > 16);
+                    assert_eq!(limit, 16);
+                }
+                _ => panic!("expected the token state limit to be exceeded"),
+            }
+
+            let options = Options::new().construction(construction).max_states(20);
+            match LexerSpec::new(&token_specs, &options) {
+                Err(Error::TooManyStates { token, states, .. }) => {
+                    assert_eq!(token, "Exploding");
+                    assert!(states > 20);
+                }
+                _ => panic!("expected the state limit to be exceeded"),
+            }
+        }
+    }
+
+    #[test]
+    fn combined_states() {
+        let token_specs = [("A", "a"), ("B", "ab|abc"), ("A", "ab"), ("C", "\\w\\w*")]
+            .into_iter()
+            .map(|(name, pattern)| TokenSpec::new(name.to_string(), pattern.to_string()))
+            .collect();
+        let options = Options::new();
+        let lexer_spec = LexerSpec::new(&token_specs, &options).unwrap();
+        let states = lexer_spec.combined_states(&options).unwrap();
+        let accepts = |lexeme: &str| {
+            let state = lexeme
+                .chars()
+                .try_fold(0, |state, c| states[state].next_state(c))?;
+            states[state].accepts.clone()
+        };
+        assert_eq!(accepts("a").as_deref(), Some("A"));
+        // `A` is specified before `B`, even though its second specification comes after.
+        assert_eq!(accepts("ab").as_deref(), Some("A"));
+        assert_eq!(accepts("abc").as_deref(), Some("B"));
+        assert_eq!(accepts("abcd").as_deref(), Some("C"));
+        assert_eq!(accepts("1"), None);
+
+        let options = Options::new().max_states(3);
+        match lexer_spec.combined_states(&options) {
+            Err(Error::TooManyStates { states, limit, .. }) => {
+                assert!(states > 3);
+                assert_eq!(limit, 3);
+            }
+            _ => panic!("expected the state limit to be exceeded"),
+        }
     }
 }
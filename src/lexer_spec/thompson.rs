@@ -0,0 +1,171 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    Error, TokenSpec,
+    lexer_spec::{DfaState, LexerSpec},
+    regex_parser::{CharRange, Regex, RegexNode},
+};
+
+/// NFA obtained with Thompson's construction.
+#[derive(Default)]
+struct Nfa {
+    states: Vec<NfaState>,
+    start_state: usize,
+    accepting_state: usize,
+}
+
+#[derive(Default)]
+struct NfaState {
+    epsilon: Vec<usize>,
+    next: Option<(CharRange, usize)>,
+}
+
+/// Set of NFA states visited by the current epsilon closure. States are marked with the closure's
+/// generation, hence starting a new closure doesn't require clearing the set.
+struct Visited {
+    generations: Vec<usize>,
+    generation: usize,
+}
+
+/// Creates a DFA from the token specification's pattern through subset construction on its
+/// Thompson NFA.
+pub fn create_dfa(token_spec: &TokenSpec, max_states: usize) -> Result<Vec<DfaState>, Error> {
+    let regex = LexerSpec::parse_regex(token_spec)?;
+    let alphabet = &regex.alphabet;
+    let nfa = Nfa::new(&regex);
+    let mut visited = Visited {
+        generations: vec![0; nfa.states.len()],
+        generation: 0,
+    };
+
+    let first_state_set = nfa.epsilon_closure(vec![nfa.start_state], &mut visited);
+    let mut state_indices = HashMap::from([(first_state_set.clone(), 0)]);
+    let mut state_sets = vec![first_state_set];
+    let mut states = vec![];
+
+    while states.len() < state_sets.len() {
+        let mut state = DfaState {
+            accepting: state_sets[states.len()].contains(&nfa.accepting_state),
+            next: vec![],
+        };
+        let mut next_state_sets = BTreeMap::new();
+        for &nfa_state in &state_sets[states.len()] {
+            let Some((range, next)) = nfa.states[nfa_state].next else {
+                continue;
+            };
+            let first = alphabet.partition_point(|r| r.end < range.start);
+            let last = alphabet.partition_point(|r| r.start <= range.end);
+            for idx in first..last {
+                let next_state_set: &mut Vec<usize> = next_state_sets.entry(idx).or_default();
+                next_state_set.push(next);
+            }
+        }
+        for (idx, next_state_set) in next_state_sets {
+            let next_state_set = nfa.epsilon_closure(next_state_set, &mut visited);
+            let next = *state_indices
+                .entry(next_state_set.clone())
+                .or_insert_with(|| {
+                    state_sets.push(next_state_set);
+                    state_sets.len() - 1
+                });
+            state.add_transition(alphabet[idx], next);
+        }
+        LexerSpec::check_num_of_states(token_spec, state_sets.len(), max_states)?;
+        states.push(state);
+    }
+    Ok(states)
+}
+
+impl Nfa {
+    fn new(regex: &Regex) -> Self {
+        let mut nfa = Self::default();
+        // Children are placed before their parent, hence the fragments of a node's children are
+        // always built before the node's own fragment.
+        let mut fragments: Vec<(usize, usize)> = Vec::with_capacity(regex.nodes.len());
+        // Whether the end state of each node's fragment is the end state of an alternation.
+        let mut alternation_ends = Vec::with_capacity(regex.nodes.len());
+        for node in &regex.nodes {
+            let mut alternation_end = false;
+            let fragment = match *node {
+                RegexNode::Cat(left, right) => {
+                    let ((left_start, left_end), (right_start, right_end)) =
+                        (fragments[left], fragments[right]);
+                    nfa.states[left_end].epsilon.push(right_start);
+                    (left_start, right_end)
+                }
+                RegexNode::Or(left, right) => {
+                    let ((left_start, left_end), (right_start, right_end)) =
+                        (fragments[left], fragments[right]);
+                    let start = nfa.new_state();
+                    nfa.states[start].epsilon.extend([left_start, right_start]);
+                    // Nested alternations, e.g. of keywords, share their end state. Otherwise, the
+                    // epsilon closure of each alternative would traverse the chain of end states.
+                    let end = if alternation_ends[right] {
+                        right_end
+                    } else if alternation_ends[left] {
+                        left_end
+                    } else {
+                        nfa.new_state()
+                    };
+                    for child_end in [left_end, right_end] {
+                        if child_end != end {
+                            nfa.states[child_end].epsilon.push(end);
+                        }
+                    }
+                    alternation_end = true;
+                    (start, end)
+                }
+                RegexNode::Parenthesized(child) => {
+                    alternation_end = alternation_ends[child];
+                    fragments[child]
+                }
+                RegexNode::Kleene(child) => {
+                    let (child_start, child_end) = fragments[child];
+                    let start = nfa.new_state();
+                    let end = nfa.new_state();
+                    nfa.states[start].epsilon.extend([child_start, end]);
+                    nfa.states[child_end].epsilon.extend([child_start, end]);
+                    (start, end)
+                }
+                RegexNode::Terminal(terminal) => {
+                    let start = nfa.new_state();
+                    match terminal.range {
+                        Some(range) => {
+                            let end = nfa.new_state();
+                            nfa.states[start].next = Some((range, end));
+                            (start, end)
+                        }
+                        None => {
+                            nfa.accepting_state = start;
+                            (start, start)
+                        }
+                    }
+                }
+            };
+            fragments.push(fragment);
+            alternation_ends.push(alternation_end);
+        }
+        nfa.start_state = fragments[regex.root()].0;
+        nfa
+    }
+
+    fn new_state(&mut self) -> usize {
+        self.states.push(NfaState::default());
+        self.states.len() - 1
+    }
+
+    /// Returns the sorted set of states reachable from `states` through epsilon transitions.
+    fn epsilon_closure(&self, mut states: Vec<usize>, visited: &mut Visited) -> Vec<usize> {
+        visited.generation += 1;
+        let mut closure = vec![];
+        while let Some(state) = states.pop() {
+            if visited.generations[state] != visited.generation {
+                visited.generations[state] = visited.generation;
+                closure.push(state);
+                states.extend(&self.states[state].epsilon);
+            }
+        }
+        closure.sort_unstable();
+        closure
+    }
+}
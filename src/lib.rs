@@ -1,39 +1,85 @@
 //! Generate lexer from token specifications.
 //!
-//! JLEK is the lexer used by [JJIK](https://github.com/fuad1502/JJIK) parser generator. Currently,
-//! JLEK cannot be used standalone without [JJIK](https://github.com/fuad1502/JJIK), since the
-//! generated lexer module (`lexer.rs`) depends on a module generated by
-//! [JJIK](https://github.com/fuad1502/JJIK) (`symbol.rs`).
+//! JLEK is the lexer used by [JJIK](https://github.com/fuad1502/JJIK) parser generator. By
+//! default, the generated lexer module (`lexer.rs`) depends on a module generated by
+//! [JJIK](https://github.com/fuad1502/JJIK) (`symbol.rs`). To use JLEK without
+//! [JJIK](https://github.com/fuad1502/JJIK), enable [Options::standalone], which makes the
+//! generated lexer module define the token types itself.
 //!
 //! # Usage
 //!
+//! JLEK is meant to be used from a build script:
+//!
 //! ```toml
-//! [dependencies]
+//! [build-dependencies]
 //! jlek = "0.1.0"
 //! ```
 //! A token specification consists of an identifier and a regular expression (see
 //! [TokenSpec](struct.TokenSpec.html)). Accepted regular expression syntax is given in [Regular
 //! Expression Syntax](crate#regular-expression-syntax).
 //!
-//! ```rust
-//! use std::path::PathBuf;
-//!
-//! // create a token specification for decimal numbers
-//! let number = jlek::TokenSpec::new("Number".to_string(), "\\d\\d*".to_string());
-//! let token_specs = vec![number];
+//! In `build.rs`, use [Builder] to generate `lexer.rs` into `$OUT_DIR`:
 //!
-//! // generate `lexer.rs` at `output_directory`
-//! let output_directory = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src");
-//! jlek::generate(&token_specs, &output_directory).unwrap();
+//! ```no_run
+//! // create a token specification for decimal numbers and generate `lexer.rs`
+//! jlek::Builder::new()
+//!     .token_spec("Number", "\\d\\d*")
+//!     .build()
+//!     .unwrap();
 //! ```
-//! You can then use the generated lexer as follows:
+//! Token specifications can also be read from a spec file with [Builder::spec_file]. Generation
+//! can be customized through [Options](struct.Options.html), e.g. [Options::file_name] and
+//! [Options::lexer_name] allow generating several lexers for the same crate. To generate into a
+//! given directory, memory or any [Write](std::io::Write) instead, see [generate],
+//! [generate_to_string] and [generate_to_writer].
+//!
+//! You can then include the generated lexer as follows:
 //!
 //! ```ignore
-//! mod lexer;
+//! mod lexer {
+//!     include!(concat!(env!("OUT_DIR"), "/lexer.rs"));
+//! }
 //!
 //! let lexer = lexer::Lexer::from_source_str("123");
 //! lexer.next_token().unwrap();
+//!
+//! // the lexer is also an iterator over the tokens, ending after the `End` token
+//! let tokens = lexer::Lexer::from_source_str("1 2 3").tokenize_all().unwrap();
 //! ```
+//! `Lexer::line_column` and `Lexer::span_line_column` map byte offsets and spans to 1-based lines
+//! and columns. Columns count characters by default, or UTF-16 code units (as used by the
+//! Language Server Protocol) with `Lexer::with_column_unit`. When counting characters, tabs
+//! advance to the next multiple of the width set with `Lexer::with_tab_width`, which defaults
+//! to 1. Lines and columns are given as a `LexerLineColumn`.
+//!
+//! Lexing errors are reported as a `LexerError`, which gives the offending character and its
+//! position, and can be rendered with the source line through `Lexer::show_error`. Spans are
+//! rendered by `Lexer::show_span`, marking the start and end line of multi-line spans, with the
+//! number of surrounding lines set by `Lexer::with_context_lines`. The rendering is colored unless
+//! the `NO_COLOR` environment variable is set or `Lexer::with_color(false)` is used.
+//! With [Options::error_recovery], the lexer instead emits an `Error` token for unexpected
+//! characters and keeps lexing, collecting every error in `Lexer::errors`.
+//!
+//! The types defined by the lexer are prefixed with its [Options::lexer_name], and the lexer
+//! imports nothing, hence lexers with different names and symbol names can share a module.
+//!
+//! The lexer borrows its source, hence lexemes returned by `get_lexeme` may outlive the lexer.
+//! To lex a file, read it with `Lexer::read_source` and pass the result to
+//! `Lexer::from_source_str`. For inputs too large to be read at once, `LexerStream::new` lexes
+//! from any [BufRead](std::io::BufRead), buffering only the current token. Its errors carry lines
+//! and columns computed like those of `Lexer`, following its own `with_tab_width` and
+//! `with_column_unit`, but it can't map other positions to lines and columns, as the input before
+//! the current token is dropped. Its spans are absolute byte offsets into the input, and its
+//! `get_lexeme` returns the lexeme as long as it is still buffered, i.e. until the next token is
+//! lexed.
+//!
+//! Whitespace between tokens is skipped. To lex significant whitespace, e.g. newlines, set the
+//! skipped characters with [Options::separators], such as
+//! `Separators::WhitespaceExcept("\\n".to_string())`, or with a `%separators` line in a spec
+//! file.
+//!
+//! With [Options::no_std], the generated lexer only depends on `core` and `alloc`, e.g. for use in
+//! embedded targets. `Lexer::read_source` and `LexerStream` are then not available.
 //! # Regular Expression Syntax
 //!
 //! ## Character classes
@@ -42,6 +88,7 @@
 //! \d      decimal digit (0-9).
 //! \w      lowercase character (a-z).
 //! \W      uppercase character (A-Z).
+//! \n      newline, and similarly `\t` for tab and `\r` for carriage return.
 //! \       escape character for matching with special characters (`\`, `*`, `|`, `(`, `)`), e.g.
 //!         `\*` matches with "*".
 //! ```
@@ -53,12 +100,21 @@
 //! x*      kleene; match with one or more occurance x.
 //! (x)     parenthesis; groups an expression for overriding precedence.
 //! ```
+//!
+//! A pattern must not match the empty string, e.g. `a*`, as such a token would be lexed forever
+//! without consuming any input.
 
+mod builder;
 mod code_gen;
+mod error;
 mod lexer_spec;
+mod options;
 mod regex_parser;
 
-pub use code_gen::generate;
+pub use builder::Builder;
+pub use code_gen::{generate, generate_to_string, generate_to_writer};
+pub use error::Error;
+pub use options::{Backend, Construction, Options, Separators};
 
 /// A token specification.
 pub struct TokenSpec {
@@ -87,3 +143,12 @@ impl TokenSpec {
         &self.name
     }
 }
+
+/// Creates an empty directory for the files generated by the test `name`.
+#[cfg(test)]
+fn test_output_directory(name: &str) -> std::path::PathBuf {
+    let output_directory = std::env::temp_dir().join(format!("jlek_test_{name}"));
+    _ = std::fs::remove_dir_all(&output_directory);
+    std::fs::create_dir_all(&output_directory).unwrap();
+    output_directory
+}
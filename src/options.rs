@@ -0,0 +1,260 @@
+/// Options for generating a lexer.
+///
+/// # Example
+///
+/// ```rust
+/// let number = jlek::TokenSpec::new("Number".to_string(), "\\d\\d*".to_string());
+/// let token_specs = vec![number];
+///
+/// let output_directory = std::env::temp_dir();
+/// jlek::Options::new()
+///     .construction(jlek::Construction::Thompson)
+///     .generate(&token_specs, &output_directory)
+///     .unwrap();
+/// ```
+///
+/// # Token types
+///
+/// Unless [Options::standalone] is set, the generated lexer imports its token types from
+/// [Options::symbol_module]. Their names are set with [Options::terminal_name],
+/// [Options::terminal_class_name] and [Options::span_name], and the generated lexer expects them
+/// to have the following shape:
+///
+/// ```ignore
+/// // Class of a token. Must have a variant named after each token specification and a variant
+/// // named `End`, and one named `Error` if [Options::error_recovery] is set. When several tokens
+/// // match the same lexeme, the smallest class is chosen.
+/// #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+/// pub enum TerminalClass { /* ... */ End }
+///
+/// // Location of a token, as byte offsets into the source.
+/// impl Span {
+///     pub fn new(start_pos: usize, end_pos: usize) -> Self;
+///     pub fn start_pos(&self) -> usize;
+///     pub fn end_pos(&self) -> usize;
+/// }
+///
+/// // A token.
+/// #[derive(Clone)]
+/// pub struct Terminal { /* ... */ }
+///
+/// impl Terminal {
+///     pub fn new(class: TerminalClass, span: Span) -> Self;
+///     pub fn span(&self) -> &Span;
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub(crate) construction: Construction,
+    pub(crate) backend: Backend,
+    pub(crate) max_states_per_token: usize,
+    pub(crate) max_states: usize,
+    pub(crate) standalone: bool,
+    pub(crate) no_std: bool,
+    pub(crate) error_recovery: bool,
+    pub(crate) separators: Separators,
+    pub(crate) symbol_module: String,
+    pub(crate) terminal_name: String,
+    pub(crate) terminal_class_name: String,
+    pub(crate) span_name: String,
+    pub(crate) file_name: String,
+    pub(crate) lexer_name: String,
+}
+
+/// Algorithm used for constructing the DFA of each token specification.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Construction {
+    /// Direct construction from the regular expression using followpos (Aho, Sethi and Ullman).
+    #[default]
+    FollowPos,
+    /// Thompson's construction of an NFA, followed by subset construction.
+    Thompson,
+}
+
+/// Code generation backend of the lexer's DFA.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// Transition table indexed by state and character class.
+    #[default]
+    Table,
+    /// A loop over a single DFA combining the DFAs of all tokens, with a `match` arm for each state
+    /// which records the token it accepts and matches the next character to jump to the next
+    /// state. This avoids table lookups at the cost of a larger generated module. The number of
+    /// combined states is also limited by [Options::max_states].
+    DirectCoded,
+}
+
+/// Characters skipped by the lexer before each token.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Separators {
+    /// Whitespace characters, as defined by [char::is_whitespace].
+    #[default]
+    Whitespace,
+    /// No characters, i.e. every character must be part of a token.
+    None,
+    /// The characters matched by a pattern of single characters and character classes, e.g.
+    /// `"\\d|_"` for digits and underscores.
+    Class(String),
+    /// Whitespace characters, except the ones matched by a pattern as in [Separators::Class],
+    /// e.g. `"\\n"` to lex newlines as tokens.
+    WhitespaceExcept(String),
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            construction: Construction::default(),
+            backend: Backend::default(),
+            max_states_per_token: 10_000,
+            max_states: 100_000,
+            standalone: false,
+            no_std: false,
+            error_recovery: false,
+            separators: Separators::default(),
+            symbol_module: "crate::symbol".to_string(),
+            terminal_name: "Terminal".to_string(),
+            terminal_class_name: "TerminalClass".to_string(),
+            span_name: "Span".to_string(),
+            file_name: "lexer.rs".to_string(),
+            lexer_name: "Lexer".to_string(),
+        }
+    }
+}
+
+impl Options {
+    /// Creates options with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the algorithm used for constructing the DFA, defaults to [Construction::FollowPos].
+    pub fn construction(mut self, construction: Construction) -> Self {
+        self.construction = construction;
+        self
+    }
+
+    /// Sets the code generation backend, defaults to [Backend::Table]. Both backends produce the
+    /// same tokens.
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Sets the maximum number of DFA states of a single token specification, defaults to 10000.
+    ///
+    /// Some regular expressions, e.g. `(a|b)*a(a|b)(a|b)(a|b)`, require a number of DFA states
+    /// exponential to their length. Generation fails with [Error::TooManyTokenStates] instead of
+    /// exhausting memory when the limit is exceeded.
+    ///
+    /// [Error::TooManyTokenStates]: crate::Error::TooManyTokenStates
+    pub fn max_states_per_token(mut self, max_states_per_token: usize) -> Self {
+        self.max_states_per_token = max_states_per_token;
+        self
+    }
+
+    /// Sets the maximum number of DFA states of all token specifications combined, defaults to
+    /// 100000. Generation fails with [Error::TooManyStates] when the limit is exceeded. It also
+    /// applies to the DFA combining them for [Backend::DirectCoded].
+    ///
+    /// [Error::TooManyStates]: crate::Error::TooManyStates
+    pub fn max_states(mut self, max_states: usize) -> Self {
+        self.max_states = max_states;
+        self
+    }
+
+    /// Sets whether the generated lexer defines its own token types, defaults to `false`.
+    ///
+    /// By default, the generated lexer imports `Span`, `Terminal` and `TerminalClass` from the
+    /// `symbol.rs` module generated by [JJIK](https://github.com/fuad1502/JJIK). In standalone
+    /// mode, these types are defined in the generated lexer instead, which allows using it without
+    /// JJIK. `TerminalClass` then has a variant for each token specification name, in order of
+    /// their priority, followed by `End`.
+    pub fn standalone(mut self, standalone: bool) -> Self {
+        self.standalone = standalone;
+        self
+    }
+
+    /// Sets whether the generated lexer only depends on `core` and `alloc`, defaults to `false`.
+    ///
+    /// This allows using the generated lexer in `#![no_std]` crates, which must then declare
+    /// `extern crate alloc;` at their root. Reading the source from a file (`Lexer::read_source`)
+    /// and lexing from a reader (`LexerStream`) require `std`, hence they are not generated, and
+    /// colored output is disabled by default since the `NO_COLOR` environment variable can't be
+    /// read.
+    pub fn no_std(mut self, no_std: bool) -> Self {
+        self.no_std = no_std;
+        self
+    }
+
+    /// Sets whether the generated lexer recovers from unexpected characters, defaults to `false`.
+    ///
+    /// By default, `next_token` fails on a character from which no token can be lexed. With error
+    /// recovery, the lexer instead emits a token of class `Error` spanning the maximal run of such
+    /// characters, and continues lexing. The errors are collected and obtained with
+    /// `Lexer::errors`. Unless [Options::standalone] is set, `TerminalClass` must then have an
+    /// `Error` variant.
+    ///
+    /// The name `Error` is then reserved: generating fails with [Error::ReservedTokenName] if a
+    /// token specification is named `Error`.
+    ///
+    /// [Error::ReservedTokenName]: crate::Error::ReservedTokenName
+    pub fn error_recovery(mut self, error_recovery: bool) -> Self {
+        self.error_recovery = error_recovery;
+        self
+    }
+
+    /// Sets the characters skipped before each token, defaults to [Separators::Whitespace].
+    ///
+    /// Separators are skipped before any token is matched, hence a token specification can only
+    /// match a separator in the middle of a lexeme. To lex significant whitespace, e.g. newlines
+    /// terminating statements, exclude it from the separators and add a token specification for
+    /// it. Generation fails with [Error::InvalidSeparators] if the pattern of
+    /// [Separators::Class] or [Separators::WhitespaceExcept] matches more than single characters.
+    ///
+    /// [Error::InvalidSeparators]: crate::Error::InvalidSeparators
+    pub fn separators(mut self, separators: Separators) -> Self {
+        self.separators = separators;
+        self
+    }
+
+    /// Sets the path of the module the token types are imported from, defaults to
+    /// `crate::symbol`. Ignored in standalone mode.
+    pub fn symbol_module(mut self, symbol_module: &str) -> Self {
+        self.symbol_module = symbol_module.to_string();
+        self
+    }
+
+    /// Sets the name of the token type, defaults to `Terminal`.
+    pub fn terminal_name(mut self, terminal_name: &str) -> Self {
+        self.terminal_name = terminal_name.to_string();
+        self
+    }
+
+    /// Sets the name of the token class type, defaults to `TerminalClass`.
+    pub fn terminal_class_name(mut self, terminal_class_name: &str) -> Self {
+        self.terminal_class_name = terminal_class_name.to_string();
+        self
+    }
+
+    /// Sets the name of the token location type, defaults to `Span`.
+    pub fn span_name(mut self, span_name: &str) -> Self {
+        self.span_name = span_name.to_string();
+        self
+    }
+
+    /// Sets the name of the file generated in the output directory, defaults to `lexer.rs`.
+    pub fn file_name(mut self, file_name: &str) -> Self {
+        self.file_name = file_name.to_string();
+        self
+    }
+
+    /// Sets the name of the generated lexer type, defaults to `Lexer`.
+    ///
+    /// Together with [Options::file_name], this allows generating several lexers for the same
+    /// crate, e.g. a `QueryLexer` in `query_lexer.rs` next to the main `Lexer` in `lexer.rs`. The
+    /// generated lexer's private types and constants are prefixed with or scoped to this name.
+    pub fn lexer_name(mut self, lexer_name: &str) -> Self {
+        self.lexer_name = lexer_name.to_string();
+        self
+    }
+}
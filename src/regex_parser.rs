@@ -1,33 +1,85 @@
 mod lexer;
 
-use std::{collections::HashSet, rc::Rc};
-
 use crate::regex_parser::lexer::{Lexer, SpecialToken, Token};
 
-#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// An inclusive range of characters.
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy, Debug)]
+pub struct CharRange {
+    pub start: char,
+    pub end: char,
+}
+
+/// A pattern position matching a character within `range`. The position without a range marks
+/// the end of the pattern.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub struct RegexTerminal {
     pub pos: usize,
-    pub ch: char,
+    pub range: Option<CharRange>,
 }
 
-#[derive(PartialEq, Eq, Hash, Debug)]
+/// Index of a node in [Regex::nodes].
+pub type NodeId = usize;
+
+#[derive(PartialEq, Eq, Debug)]
 pub enum RegexNode {
-    Cat(Rc<RegexNode>, Rc<RegexNode>),
-    Or(Rc<RegexNode>, Rc<RegexNode>),
-    Parenthesized(Rc<RegexNode>),
-    Kleene(Rc<RegexNode>),
+    Cat(NodeId, NodeId),
+    Or(NodeId, NodeId),
+    Parenthesized(NodeId),
+    Kleene(NodeId),
     Terminal(RegexTerminal),
 }
 
-pub fn parse_regex(pattern: &str) -> Result<(Rc<RegexNode>, HashSet<char>), String> {
+/// A parsed regular expression, augmented with a terminal marking the end of the pattern.
+#[derive(Debug)]
+pub struct Regex {
+    /// Arena of nodes. Children are always placed before their parent, so the last node is the
+    /// root.
+    pub nodes: Vec<RegexNode>,
+    /// Terminal of each position, indexed by position.
+    pub terminals: Vec<RegexTerminal>,
+    /// Disjoint character ranges matched by the terminals, sorted by range.
+    pub alphabet: Vec<CharRange>,
+}
+
+pub fn parse_regex(pattern: &str) -> Result<Regex, String> {
     let parser = RegexParser::new(pattern);
     parser.parse()
 }
 
+/// Parses a pattern matching single characters, i.e. a disjunction of characters and character
+/// classes, into the sorted, disjoint ranges of the matched characters.
+pub fn parse_char_class(pattern: &str) -> Result<Vec<CharRange>, String> {
+    let regex = parse_regex(pattern)?;
+    let RegexNode::Cat(class, _) = regex.nodes[regex.root()] else {
+        unreachable!("the root of a regex is its augmentation")
+    };
+    let mut ranges = vec![];
+    let mut nodes = vec![class];
+    while let Some(node) = nodes.pop() {
+        match regex.nodes[node] {
+            RegexNode::Or(left, right) => nodes.extend([left, right]),
+            RegexNode::Parenthesized(node) => nodes.push(node),
+            RegexNode::Terminal(RegexTerminal {
+                range: Some(range), ..
+            }) => ranges.push(range),
+            _ => return Err("Expected a single character or character class".to_string()),
+        }
+    }
+    // Merge the adjacent ranges of the partition, e.g. those of an overlapping `\w|e`.
+    let mut class: Vec<CharRange> = vec![];
+    for range in CharRange::partition(&ranges) {
+        match class.last_mut() {
+            Some(last) if last.end as u32 + 1 == range.start as u32 => last.end = range.end,
+            _ => class.push(range),
+        }
+    }
+    Ok(class)
+}
+
 struct RegexParser {
     lexer: Lexer,
-    current_pos: usize,
-    alphabet: HashSet<char>,
+    nodes: Vec<RegexNode>,
+    terminals: Vec<RegexTerminal>,
 }
 
 // P -> P1
@@ -42,48 +94,58 @@ impl RegexParser {
         let lexer = Lexer::new(pattern);
         Self {
             lexer,
-            current_pos: 0,
-            alphabet: HashSet::new(),
+            nodes: vec![],
+            terminals: vec![],
         }
     }
 
-    fn parse(mut self) -> Result<(Rc<RegexNode>, HashSet<char>), String> {
+    fn parse(mut self) -> Result<Regex, String> {
         let p1 = self.p1()?;
         if *self.lexer.peek()? != Token::End {
             return Err("Expected EOF".to_string());
         }
-        Ok((self.augment(p1), self.alphabet))
+        self.augment(p1);
+        let ranges = self
+            .terminals
+            .iter()
+            .filter_map(|t| t.range)
+            .collect::<Vec<CharRange>>();
+        Ok(Regex {
+            nodes: self.nodes,
+            terminals: self.terminals,
+            alphabet: CharRange::partition(&ranges),
+        })
     }
 
-    fn p1(&mut self) -> Result<Rc<RegexNode>, String> {
+    fn p1(&mut self) -> Result<NodeId, String> {
         let mut p1 = self.p2()?;
         while *self.lexer.peek()? == Token::Or {
             _ = self.lexer.next()?;
             let p2 = self.p2()?;
-            p1 = Self::or(p1, p2);
+            p1 = self.or(p1, p2);
         }
         Ok(p1)
     }
 
-    fn p2(&mut self) -> Result<Rc<RegexNode>, String> {
+    fn p2(&mut self) -> Result<NodeId, String> {
         let mut p2 = self.p3()?;
         while !Self::is_in_follow_p2(self.lexer.peek()?) {
             let p3 = self.p3()?;
-            p2 = Self::cat(p2, p3);
+            p2 = self.cat(p2, p3);
         }
         Ok(p2)
     }
 
-    fn p3(&mut self) -> Result<Rc<RegexNode>, String> {
+    fn p3(&mut self) -> Result<NodeId, String> {
         let mut p3 = self.p4()?;
         if *self.lexer.peek()? == Token::Star {
             _ = self.lexer.next();
-            p3 = Self::kleene(p3);
+            p3 = self.kleene(p3);
         }
         Ok(p3)
     }
 
-    fn p4(&mut self) -> Result<Rc<RegexNode>, String> {
+    fn p4(&mut self) -> Result<NodeId, String> {
         if *self.lexer.peek()? == Token::LeftParen {
             _ = self.lexer.next()?;
             let p1 = self.p1()?;
@@ -91,18 +153,18 @@ impl RegexParser {
                 return Err("Expected closing right parenthesis".to_string());
             }
             _ = self.lexer.next()?;
-            Ok(Self::parenthesized(p1))
+            Ok(self.parenthesized(p1))
         } else {
             self.p5()
         }
     }
 
-    fn p5(&mut self) -> Result<Rc<RegexNode>, String> {
+    fn p5(&mut self) -> Result<NodeId, String> {
         match self.lexer.next()? {
-            Token::Char(ch) => Ok(self.single_char(ch)),
-            Token::Special(SpecialToken::Number) => Ok(self.number()),
-            Token::Special(SpecialToken::Lowercase) => Ok(self.lowercase()),
-            Token::Special(SpecialToken::Uppercase) => Ok(self.uppercase()),
+            Token::Char(ch) => Ok(self.range(ch, ch)),
+            Token::Special(SpecialToken::Number) => Ok(self.range('0', '9')),
+            Token::Special(SpecialToken::Lowercase) => Ok(self.range('a', 'z')),
+            Token::Special(SpecialToken::Uppercase) => Ok(self.range('A', 'Z')),
             _ => Err("Expected (special) character".to_string()),
         }
     }
@@ -111,87 +173,139 @@ impl RegexParser {
         *token == Token::Or || *token == Token::End || *token == Token::RightParen
     }
 
-    fn augment(&mut self, node: Rc<RegexNode>) -> Rc<RegexNode> {
-        let sentinel = Rc::new(RegexNode::terminal('\0', self.current_pos));
-        self.current_pos += 1;
-        Self::cat(node, sentinel)
+    fn augment(&mut self, node: NodeId) -> NodeId {
+        let sentinel = self.terminal(None);
+        self.cat(node, sentinel)
     }
 
-    fn number(&mut self) -> Rc<RegexNode> {
-        let mut node = None;
-        for i in 0..10 {
-            let ch = char::from_digit(i, 10).unwrap();
-            match node {
-                None => node = Some(self.single_char(ch)),
-                Some(n) => node = Some(Self::or(n, self.single_char(ch))),
-            }
-            self.alphabet.insert(ch);
-        }
-        node.unwrap()
+    fn range(&mut self, start: char, end: char) -> NodeId {
+        self.terminal(Some(CharRange::new(start, end)))
     }
 
-    fn lowercase(&mut self) -> Rc<RegexNode> {
-        let mut node = None;
-        for ch in 'a'..='z' {
-            match node {
-                None => node = Some(self.single_char(ch)),
-                Some(n) => node = Some(Self::or(n, self.single_char(ch))),
-            }
-            self.alphabet.insert(ch);
-        }
-        node.unwrap()
+    fn terminal(&mut self, range: Option<CharRange>) -> NodeId {
+        let terminal = RegexTerminal {
+            pos: self.terminals.len(),
+            range,
+        };
+        self.terminals.push(terminal);
+        self.node(RegexNode::Terminal(terminal))
     }
 
-    fn uppercase(&mut self) -> Rc<RegexNode> {
-        let mut node = None;
-        for ch in 'A'..='Z' {
-            match node {
-                None => node = Some(self.single_char(ch)),
-                Some(n) => node = Some(Self::or(n, self.single_char(ch))),
-            }
-            self.alphabet.insert(ch);
-        }
-        node.unwrap()
+    fn cat(&mut self, left: NodeId, right: NodeId) -> NodeId {
+        self.node(RegexNode::Cat(left, right))
     }
 
-    fn single_char(&mut self, ch: char) -> Rc<RegexNode> {
-        let char = Rc::new(RegexNode::terminal(ch, self.current_pos));
-        self.current_pos += 1;
-        self.alphabet.insert(ch);
-        char
+    fn or(&mut self, left: NodeId, right: NodeId) -> NodeId {
+        self.node(RegexNode::Or(left, right))
     }
 
-    fn cat(left: Rc<RegexNode>, right: Rc<RegexNode>) -> Rc<RegexNode> {
-        Rc::new(RegexNode::Cat(left, right))
+    fn parenthesized(&mut self, node: NodeId) -> NodeId {
+        self.node(RegexNode::Parenthesized(node))
     }
 
-    fn or(left: Rc<RegexNode>, right: Rc<RegexNode>) -> Rc<RegexNode> {
-        Rc::new(RegexNode::Or(left, right))
+    fn kleene(&mut self, node: NodeId) -> NodeId {
+        self.node(RegexNode::Kleene(node))
     }
 
-    fn parenthesized(node: Rc<RegexNode>) -> Rc<RegexNode> {
-        Rc::new(RegexNode::Parenthesized(node))
+    fn node(&mut self, node: RegexNode) -> NodeId {
+        self.nodes.push(node);
+        self.nodes.len() - 1
     }
+}
 
-    fn kleene(node: Rc<RegexNode>) -> Rc<RegexNode> {
-        Rc::new(RegexNode::Kleene(node))
+impl Regex {
+    pub fn root(&self) -> NodeId {
+        self.nodes.len() - 1
+    }
+}
+
+impl CharRange {
+    pub fn new(start: char, end: char) -> Self {
+        Self { start, end }
+    }
+
+    /// Splits the characters covered by `ranges` into sorted, disjoint ranges, such that each
+    /// resulting range is either fully contained in or disjoint from every range in `ranges`.
+    pub fn partition(ranges: &[CharRange]) -> Vec<CharRange> {
+        // Sweep over range boundaries, tracking how many ranges cover the current position.
+        let mut boundaries = ranges
+            .iter()
+            .flat_map(|r| [(r.start as u32, 1), (r.end as u32 + 1, -1)])
+            .collect::<Vec<(u32, i32)>>();
+        boundaries.sort_unstable();
+
+        let mut partition = vec![];
+        let mut coverage = 0;
+        for (i, &(boundary, delta)) in boundaries.iter().enumerate() {
+            coverage += delta;
+            let Some(&(next_boundary, _)) = boundaries.get(i + 1) else {
+                break;
+            };
+            if coverage == 0 || boundary == next_boundary {
+                continue;
+            }
+            // Skip over surrogate code points, which are not valid characters.
+            let start = char::from_u32(boundary).unwrap_or('\u{E000}');
+            let end = char::from_u32(next_boundary - 1).unwrap_or('\u{D7FF}');
+            if start <= end {
+                partition.push(CharRange { start, end });
+            }
+        }
+        partition
     }
 }
 
 #[cfg(test)]
 mod test {
-    use std::collections::HashSet;
-
-    use crate::regex_parser::parse_regex;
+    use crate::regex_parser::{CharRange, parse_char_class, parse_regex};
 
     #[test]
     fn main() {
-        let (_, alphabet) = parse_regex("a(bc)*|\\d").unwrap();
+        let regex = parse_regex("a(bc)*|\\d").unwrap();
+        assert_eq!(
+            regex.alphabet,
+            vec![
+                CharRange::new('0', '9'),
+                CharRange::new('a', 'a'),
+                CharRange::new('b', 'b'),
+                CharRange::new('c', 'c'),
+            ]
+        );
+    }
+
+    #[test]
+    fn partition() {
+        let ranges = [
+            CharRange::new('a', 'z'),
+            CharRange::new('e', 'e'),
+            CharRange::new('x', 'x'),
+            CharRange::new('0', '9'),
+        ];
+        assert_eq!(
+            CharRange::partition(&ranges),
+            vec![
+                CharRange::new('0', '9'),
+                CharRange::new('a', 'd'),
+                CharRange::new('e', 'e'),
+                CharRange::new('f', 'w'),
+                CharRange::new('x', 'x'),
+                CharRange::new('y', 'z'),
+            ]
+        );
+    }
+
+    #[test]
+    fn char_class() {
         assert_eq!(
-            alphabet,
-            HashSet::from([
-                'a', 'b', 'c', '0', '1', '2', '3', '4', '5', '6', '7', '8', '9'
-            ])
+            parse_char_class("\\w|e|(\\t|\\d)").unwrap(),
+            vec![
+                CharRange::new('\t', '\t'),
+                CharRange::new('0', '9'),
+                CharRange::new('a', 'z'),
+            ]
         );
+        assert!(parse_char_class("ab").is_err());
+        assert!(parse_char_class("a*").is_err());
+        assert!(parse_char_class("").is_err());
     }
 }
@@ -61,6 +61,9 @@ impl Lexer {
             Some(ch) if ch == '*' || ch == '|' || ch == '(' || ch == ')' || ch == '\\' => {
                 Ok(Token::Char(ch))
             }
+            Some('n') => Ok(Token::Char('\n')),
+            Some('t') => Ok(Token::Char('\t')),
+            Some('r') => Ok(Token::Char('\r')),
             Some('d') => Ok(Token::Special(SpecialToken::Number)),
             Some('w') => Ok(Token::Special(SpecialToken::Lowercase)),
             Some('W') => Ok(Token::Special(SpecialToken::Uppercase)),
@@ -90,5 +93,11 @@ mod test {
         assert_eq!(lexer.next().unwrap(), Token::Char('c'));
         assert_eq!(lexer.next().unwrap(), Token::RightParen);
         assert_eq!(lexer.next().unwrap(), Token::End);
+
+        let mut lexer = Lexer::new("\\n\\t\\r\\*");
+        assert_eq!(lexer.next().unwrap(), Token::Char('\n'));
+        assert_eq!(lexer.next().unwrap(), Token::Char('\t'));
+        assert_eq!(lexer.next().unwrap(), Token::Char('\r'));
+        assert_eq!(lexer.next().unwrap(), Token::Char('*'));
     }
 }
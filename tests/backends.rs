@@ -0,0 +1,323 @@
+//! Runs the lexers generated into `tests/fixtures`. Checks that both backends, the `no_std` lexer
+//! and `LexerStream` produce the same tokens, and checks the lexeme, position, rendering, error
+//! recovery and separator APIs of the generated lexers.
+
+extern crate alloc;
+
+use std::io::BufReader;
+
+#[allow(dead_code)]
+mod table_lexer {
+    include!("fixtures/table_lexer.rs");
+}
+
+#[allow(dead_code)]
+mod direct_coded_lexer {
+    include!("fixtures/direct_coded_lexer.rs");
+}
+
+#[allow(dead_code)]
+mod recovery_lexer {
+    include!("fixtures/recovery_lexer.rs");
+}
+
+#[allow(dead_code)]
+mod separators_lexer {
+    include!("fixtures/separators_lexer.rs");
+}
+
+#[allow(dead_code)]
+mod no_std_lexer {
+    include!("fixtures/no_std_lexer.rs");
+}
+
+/// Lexes the source of `lexer` into the class name and span of each token.
+macro_rules! tokenize {
+    ($lexer:expr) => {
+        $lexer
+            .map(|token| {
+                token
+                    .map(|token| {
+                        let span = token.span();
+                        (
+                            format!("{:?}", token.class()),
+                            span.start_pos(),
+                            span.end_pos(),
+                        )
+                    })
+                    .map_err(|error| error.to_string())
+            })
+            .collect::<Vec<_>>()
+    };
+}
+
+const SOURCES: [&str; 8] = [
+    "if iffy 123 x9 * -> - 'ab' bc a\n  Foo",
+    "a bc abc ifif if9 0 007 --> ''",
+    "abcdefghijklmnop123 if",
+    "'ab\n-",
+    "x $",
+    "αβγ x αα\n β",
+    "x ü",
+    "",
+];
+
+#[test]
+fn identical_tokens() {
+    for source in SOURCES {
+        assert_eq!(
+            tokenize!(table_lexer::Lexer::from_source_str(source)),
+            tokenize!(direct_coded_lexer::Lexer::from_source_str(source)),
+            "{source:?}"
+        );
+        assert_eq!(
+            tokenize!(table_lexer::Lexer::from_source_str(source)),
+            tokenize!(no_std_lexer::Lexer::from_source_str(source)),
+            "{source:?}"
+        );
+    }
+}
+
+#[test]
+fn stream() {
+    for source in SOURCES {
+        // A small buffer capacity makes tokens span several refills of the stream's buffer.
+        let reader = BufReader::with_capacity(3, source.as_bytes());
+        let streamed = tokenize!(table_lexer::LexerStream::new(reader));
+        let tokens = tokenize!(table_lexer::Lexer::from_source_str(source));
+        assert_eq!(streamed, tokens, "{source:?}");
+    }
+
+    let reader = BufReader::with_capacity(2, "abcdefgh αβγ".as_bytes());
+    let mut lexer = table_lexer::LexerStream::new(reader);
+    let token = lexer.next_token().unwrap();
+    assert_eq!(lexer.get_lexeme(&token), Some("abcdefgh"));
+    let token = lexer.next_token().unwrap();
+    assert_eq!(token.span().start_pos(), 9);
+    assert_eq!(lexer.get_lexeme(&token), Some("αβγ"));
+
+    let mut lexer = table_lexer::LexerStream::new(&[b'x', b' ', 0xFF][..]);
+    lexer.next_token().unwrap();
+    let error = lexer.next_token().unwrap_err();
+    assert!(matches!(
+        error.kind(),
+        table_lexer::LexerErrorKind::InvalidUtf8
+    ));
+    assert_eq!(error.to_string(), "invalid UTF-8 at line 1, column 3");
+
+    let source = "if\tx\r\n\t𝄞 $";
+    let mut lexer = table_lexer::LexerStream::new(BufReader::with_capacity(2, source.as_bytes()))
+        .with_tab_width(4)
+        .with_column_unit(table_lexer::LexerColumnUnit::Utf16);
+    let error = lexer.tokenize_all().unwrap_err();
+    let mut expected_lexer = table_lexer::Lexer::from_source_str(source)
+        .with_tab_width(4)
+        .with_column_unit(table_lexer::LexerColumnUnit::Utf16);
+    let expected_error = expected_lexer.tokenize_all().unwrap_err();
+    assert_eq!(error.to_string(), expected_error.to_string());
+    assert_eq!(error.span(), expected_error.span());
+}
+
+#[test]
+fn tokenize_all() {
+    use table_lexer::TerminalClass;
+
+    let mut lexer = table_lexer::Lexer::from_source_str("if 42");
+    let tokens = lexer.tokenize_all().unwrap();
+    assert_eq!(
+        tokens.iter().map(|token| token.class()).collect::<Vec<_>>(),
+        [TerminalClass::If, TerminalClass::Number, TerminalClass::End]
+    );
+    assert!(lexer.next().is_none());
+
+    let mut lexer = table_lexer::Lexer::from_source_str("if $ 42");
+    assert!(lexer.tokenize_all().is_err());
+    assert!(lexer.next().is_none());
+}
+
+#[test]
+fn utf8() {
+    let source = "αβ x\n ünï";
+    let mut lexer = table_lexer::Lexer::from_source_str(source).with_color(true);
+    let token = lexer.next_token().unwrap();
+    assert_eq!(token.class(), table_lexer::TerminalClass::Greek);
+    assert_eq!(lexer.get_lexeme(&token), "αβ");
+    let token = lexer.next_token().unwrap();
+    assert_eq!(lexer.get_lexeme(&token), "x");
+    assert_eq!(token.span().start_pos(), "αβ ".len());
+    let error = lexer.next_token().unwrap_err();
+    assert_eq!(error.found(), Some('ü'));
+    assert_eq!(error.byte_pos(), "αβ x\n ".len());
+    assert_eq!((error.line(), error.column()), (2, 2));
+    assert_eq!(
+        error.to_string(),
+        "unexpected character 'ü' at line 2, column 2"
+    );
+    assert!(
+        lexer
+            .show_error(&error)
+            .contains("\n         \x1B[31m ^\x1B[0m")
+    );
+}
+
+#[test]
+fn borrowed_lexemes() {
+    let source = String::from("if x1 42");
+    let lexemes = {
+        let mut lexer = direct_coded_lexer::Lexer::from_source_str(&source);
+        let mut lexemes = vec![];
+        for _ in 0..3 {
+            let token = lexer.next_token().unwrap();
+            lexemes.push(lexer.get_lexeme(&token));
+        }
+        lexemes
+    };
+    assert_eq!(lexemes, ["if", "x1", "42"]);
+}
+
+#[test]
+fn read_source() {
+    let source_file = std::env::temp_dir().join("jlek_test_read_source.txt");
+    std::fs::write(&source_file, "if x1").unwrap();
+    let source = table_lexer::Lexer::read_source(&source_file).unwrap();
+    let mut lexer = table_lexer::Lexer::from_source_str(&source);
+    let token = lexer.next_token().unwrap();
+    assert_eq!(lexer.get_lexeme(&token), "if");
+
+    let missing_file = source_file.with_extension("missing");
+    assert!(table_lexer::Lexer::read_source(&missing_file).is_err());
+}
+
+#[test]
+fn line_column() {
+    use table_lexer::{LexerColumnUnit, LexerLineColumn};
+
+    let source = "if\tx\r\n𝄞 42";
+    let x_pos = "if\t".len();
+    let number_pos = "if\tx\r\n𝄞 ".len();
+    let lexer = table_lexer::Lexer::from_source_str(source);
+    assert_eq!(
+        lexer.line_column(x_pos),
+        LexerLineColumn { line: 1, column: 4 }
+    );
+    assert_eq!(
+        lexer.line_column(number_pos),
+        LexerLineColumn { line: 2, column: 3 }
+    );
+    assert!(
+        !lexer
+            .show_span(&table_lexer::Span::new(0, 2))
+            .contains('\r')
+    );
+
+    let lexer = lexer.with_tab_width(4);
+    assert_eq!(
+        lexer.line_column(x_pos),
+        LexerLineColumn { line: 1, column: 5 }
+    );
+
+    // A tab is a single UTF-16 code unit, whatever the tab width.
+    let lexer = lexer.with_column_unit(LexerColumnUnit::Utf16);
+    assert_eq!(
+        lexer.line_column(x_pos),
+        LexerLineColumn { line: 1, column: 4 }
+    );
+    assert_eq!(
+        lexer.span_line_column(&table_lexer::Span::new(number_pos, source.len())),
+        (
+            LexerLineColumn { line: 2, column: 4 },
+            LexerLineColumn { line: 2, column: 6 }
+        )
+    );
+}
+
+#[test]
+fn show_span() {
+    let source = "a\nif 'ab\ncd\nef' x\nlast";
+    let span = table_lexer::Span::new("a\nif ".len(), "a\nif 'ab\ncd\nef'".len());
+    let lexer = table_lexer::Lexer::from_source_str(source).with_color(false);
+    assert_eq!(
+        lexer.show_span(&span),
+        "Line   2|if 'ab\n            ^--\nLine   3|cd\nLine   4|ef' x\n         --^"
+    );
+    let lexer = lexer.with_context_lines(1);
+    assert_eq!(
+        lexer.show_span(&table_lexer::Span::new(0, 1)),
+        "Line   1|a\n         ^\nLine   2|if 'ab"
+    );
+    assert_eq!(
+        lexer.show_span(&table_lexer::Span::new(source.len() - 1, source.len())),
+        "Line   4|ef' x\nLine   5|last\n            ^"
+    );
+}
+
+#[test]
+fn error_recovery() {
+    let source = "if $$ 42 ü-> 'ab x";
+    let expected = [
+        ("If", 0, 2),
+        ("Error", 3, 5),
+        ("Number", 6, 8),
+        ("Error", 9, 11),
+        ("Arrow", 11, 13),
+        ("Error", 14, 15),
+        ("Identifier", 15, 17),
+        ("Identifier", 18, 19),
+        ("End", 19, 19),
+    ]
+    .map(|(class, start, end)| Ok((class.to_string(), start, end)));
+
+    let mut lexer = recovery_lexer::Lexer::from_source_str(source);
+    assert_eq!(tokenize!(lexer.by_ref()), expected);
+    let errors = lexer
+        .errors()
+        .iter()
+        .map(|error| {
+            (
+                error.found(),
+                error.span().start_pos(),
+                error.span().end_pos(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        [(Some('$'), 3, 5), (Some('ü'), 9, 11), (Some('\''), 14, 15)]
+    );
+
+    let reader = BufReader::with_capacity(3, source.as_bytes());
+    let mut lexer = recovery_lexer::LexerStream::new(reader);
+    assert_eq!(tokenize!(lexer.by_ref()), expected);
+    assert_eq!(lexer.errors().len(), 3);
+}
+
+#[test]
+fn separators() {
+    let source = "if x\n\t42\n\n";
+    let expected = [
+        ("If", 0, 2),
+        ("Identifier", 3, 4),
+        ("Newline", 4, 5),
+        ("Number", 6, 8),
+        ("Newline", 8, 9),
+        ("Newline", 9, 10),
+        ("End", 10, 10),
+    ]
+    .map(|(class, start, end)| Ok((class.to_string(), start, end)));
+    assert_eq!(
+        tokenize!(separators_lexer::Lexer::from_source_str(source)),
+        expected
+    );
+    let reader = BufReader::with_capacity(3, source.as_bytes());
+    assert_eq!(
+        tokenize!(separators_lexer::LexerStream::new(reader)),
+        expected
+    );
+
+    // Every whitespace character other than the newline is still skipped.
+    let mut lexer = separators_lexer::Lexer::from_source_str("x\r\u{A0}\n");
+    lexer.next_token().unwrap();
+    let token = lexer.next_token().unwrap();
+    assert_eq!(token.class(), separators_lexer::TerminalClass::Newline);
+    assert_eq!(lexer.get_lexeme(&token), "\n");
+}
@@ -0,0 +1,10 @@
+use jlek::TokenSpec;
+
+/// Token specifications of the lexers generated into `tests/fixtures`.
+pub fn token_specs() -> Vec<TokenSpec> {
+    let token_specs: [(&str, &str); 8] = include!("token_specs.rs");
+    token_specs
+        .into_iter()
+        .map(|(name, pattern)| TokenSpec::new(name.to_string(), pattern.to_string()))
+        .collect()
+}
@@ -0,0 +1,12 @@
+// Names and patterns of the token specifications of the lexers generated into `tests/fixtures`.
+// Included as an expression by `common::token_specs` and by the unit tests of the constructions.
+[
+    ("If", "if"),
+    ("Number", "\\d\\d*"),
+    ("Identifier", "(\\w|\\W)(\\w|\\W|\\d)*"),
+    ("Star", "\\*"),
+    ("Arrow", "-|->"),
+    ("Quote", "'(\\w)*'"),
+    ("Alternation", "a|bc"),
+    ("Greek", "(α|β|γ)(α|β|γ)*"),
+]
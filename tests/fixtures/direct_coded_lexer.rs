@@ -0,0 +1,674 @@
+// @generated by JLEK 0.1.3, fingerprint 027209525db0c2f1. Do not edit.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TerminalClass {
+    If,
+    Number,
+    Identifier,
+    Star,
+    Arrow,
+    Quote,
+    Alternation,
+    Greek,
+    End,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Span {
+    start_pos: usize,
+    end_pos: usize,
+}
+
+impl Span {
+    pub fn new(start_pos: usize, end_pos: usize) -> Self {
+        Self { start_pos, end_pos }
+    }
+
+    pub fn start_pos(&self) -> usize {
+        self.start_pos
+    }
+
+    pub fn end_pos(&self) -> usize {
+        self.end_pos
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Terminal {
+    class: TerminalClass,
+    span: Span,
+}
+
+impl Terminal {
+    pub fn new(class: TerminalClass, span: Span) -> Self {
+        Self { class, span }
+    }
+
+    pub fn class(&self) -> TerminalClass {
+        self.class
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+}
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LexerLineColumn {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LexerColumnUnit {
+    Char,
+    Utf16,
+}
+
+#[derive(Debug)]
+pub struct LexerError {
+    kind: LexerErrorKind,
+    start_pos: usize,
+    end_pos: usize,
+    line: usize,
+    column: usize,
+}
+
+#[derive(Debug)]
+pub enum LexerErrorKind {
+    UnexpectedChar(char),
+    InvalidUtf8,
+    Io(std::io::Error),
+}
+
+impl LexerError {
+    pub fn kind(&self) -> &LexerErrorKind {
+        &self.kind
+    }
+
+    pub fn span(&self) -> Span {
+        Span::new(self.start_pos, self.end_pos)
+    }
+
+    pub fn found(&self) -> Option<char> {
+        match self.kind {
+            LexerErrorKind::UnexpectedChar(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    pub fn byte_pos(&self) -> usize {
+        self.start_pos
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl std::fmt::Display for LexerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            LexerErrorKind::UnexpectedChar(c) => write!(f, "unexpected character {c:?}")?,
+            LexerErrorKind::InvalidUtf8 => write!(f, "invalid UTF-8")?,
+            LexerErrorKind::Io(error) => write!(f, "{error}")?,
+        }
+        write!(f, " at line {}, column {}", self.line, self.column)
+    }
+}
+
+impl std::error::Error for LexerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            LexerErrorKind::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+pub struct Lexer<'src> {
+    source: &'src str,
+    driver: LexerDriver<&'src str>,
+    line_start_indices: Vec<usize>,
+    context_lines: usize,
+    color: bool,
+}
+
+impl<'src> Lexer<'src> {
+
+    pub fn from_source_str(source: &'src str) -> Self {
+        let mut line_start_indices = source
+            .bytes()
+            .enumerate()
+            .filter_map(|(i, c)| if c == b'\n' { Some(i + 1) } else { None })
+            .collect::<Vec<usize>>();
+        line_start_indices.insert(0, 0);
+        Self {
+            source,
+            driver: LexerDriver::new(source),
+            line_start_indices,
+            context_lines: 0,
+            color: std::env::var_os("NO_COLOR").is_none_or(|no_color| no_color.is_empty()),
+        }
+    }
+
+    pub fn read_source(source_file: &std::path::Path) -> Result<String, std::io::Error> {
+        std::fs::read_to_string(source_file)
+    }
+
+    pub fn with_tab_width(mut self, tab_width: usize) -> Self {
+        self.driver.tab_width = tab_width.max(1);
+        self
+    }
+
+    pub fn with_column_unit(mut self, column_unit: LexerColumnUnit) -> Self {
+        self.driver.column_unit = column_unit;
+        self
+    }
+
+    pub fn with_context_lines(mut self, context_lines: usize) -> Self {
+        self.context_lines = context_lines;
+        self
+    }
+
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn next_token(&mut self) -> Result<Terminal, LexerError> {
+        self.driver.next_token()
+    }
+
+    pub fn peek_token(&mut self) -> Result<&Terminal, LexerError> {
+        self.driver.peek_token()
+    }
+
+    pub fn tokenize_all(&mut self) -> Result<Vec<Terminal>, LexerError> {
+        self.by_ref().collect()
+    }
+
+    pub fn get_lexeme(&self, token: &Terminal) -> &'src str {
+        self.str_from_span(token.span())
+    }
+
+    pub fn str_from_span(&self, span: &Span) -> &'src str {
+        &self.source[span.start_pos()..span.end_pos()]
+    }
+
+    pub fn line_column(&self, pos: usize) -> LexerLineColumn {
+        let line = self.line_start_indices.partition_point(|&i| i <= pos);
+        let line_start_idx = self.line_start_indices[line - 1];
+        self.source[line_start_idx..pos]
+            .chars()
+            .fold(LexerLineColumn { line, column: 1 }, |line_column, c| {
+                self.driver.advance(line_column, c)
+            })
+    }
+
+    pub fn span_line_column(&self, span: &Span) -> (LexerLineColumn, LexerLineColumn) {
+        (
+            self.line_column(span.start_pos()),
+            self.line_column(span.end_pos()),
+        )
+    }
+
+    pub fn show_span(&self, span: &Span) -> String {
+        let (blue, red, end) = self.colors();
+        let first_line = self
+            .line_start_indices
+            .partition_point(|&i| i <= span.start_pos());
+        let last_line = self
+            .line_start_indices
+            .partition_point(|&i| i < span.end_pos())
+            .max(first_line);
+        let context_first_line = first_line.saturating_sub(self.context_lines).max(1);
+        let context_last_line =
+            (last_line + self.context_lines).min(self.line_start_indices.len());
+        let mut rendered_lines = vec![];
+        for line_number in context_first_line..=context_last_line {
+            let (line_start_idx, line) = self.line(line_number);
+            rendered_lines.push(format!("{blue}Line {line_number:3}|{end}{line}"));
+            if line_number != first_line && line_number != last_line {
+                continue;
+            }
+            let line_end_idx = line_start_idx + line.len();
+            let marker_start =
+                span.start_pos().clamp(line_start_idx, line_end_idx) - line_start_idx;
+            let marker_end = span.end_pos().clamp(line_start_idx, line_end_idx) - line_start_idx;
+            // Tabs are kept in the indentation, so that the marker lines up with the line.
+            let indent = line[..marker_start]
+                .chars()
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect::<String>();
+            let marked_length = line[marker_start..marker_end].chars().count();
+            let dashes = "-".repeat(marked_length.saturating_sub(1));
+            let marker = if line_number == first_line {
+                format!("^{dashes}")
+            } else {
+                format!("{dashes}^")
+            };
+            rendered_lines.push(format!("         {red}{indent}{marker}{end}"));
+        }
+        rendered_lines.join("\n")
+    }
+
+    pub fn show_error(&self, error: &LexerError) -> String {
+        let (_, red, end) = self.colors();
+        let span_str = self.show_span(&error.span());
+        format!("{span_str}\n{red}error{end}: {error}")
+    }
+
+    fn line(&self, line_number: usize) -> (usize, &'src str) {
+        let line_start_idx = self.line_start_indices[line_number - 1];
+        let line_end_idx = match self.line_start_indices.get(line_number) {
+            Some(idx) => idx - 1,
+            None => self.source.len(),
+        };
+        let line = self.source[line_start_idx..line_end_idx].trim_end_matches('\r');
+        (line_start_idx, line)
+    }
+
+    fn colors(&self) -> (&'static str, &'static str, &'static str) {
+        if self.color {
+            ("\x1B[34m", "\x1B[31m", "\x1B[0m")
+        } else {
+            ("", "", "")
+        }
+    }
+
+    fn is_separator(c: char) -> bool {
+        c.is_whitespace()
+    }
+}
+
+impl<'src> Iterator for Lexer<'src> {
+    type Item = Result<Terminal, LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.driver.next()
+    }
+}
+
+/// Input of a `LexerDriver`, indexed by byte offsets.
+trait LexerInput {
+    /// Obtains the character at `pos`. The input before `start_pos`, the start of the current
+    /// token, is no longer needed.
+    fn char_at(&mut self, pos: usize, start_pos: usize) -> Result<Option<char>, LexerErrorKind>;
+
+    /// Obtains the input from `start_pos` to `end_pos`, which must have been read and must not be
+    /// before the current token.
+    fn slice(&self, start_pos: usize, end_pos: usize) -> &str;
+}
+
+impl LexerInput for &str {
+    fn char_at(&mut self, pos: usize, _: usize) -> Result<Option<char>, LexerErrorKind> {
+        Ok(self[pos..].chars().next())
+    }
+
+    fn slice(&self, start_pos: usize, end_pos: usize) -> &str {
+        &self[start_pos..end_pos]
+    }
+}
+
+/// Lexes the tokens of an input, for both `Lexer` and its streaming counterpart.
+struct LexerDriver<I> {
+    input: I,
+    start_pos: usize,
+    start_line_column: LexerLineColumn,
+    current_pos: usize,
+    current_token: Option<Terminal>,
+    reached_end: bool,
+    finished: bool,
+    tab_width: usize,
+    column_unit: LexerColumnUnit,
+}
+
+impl<I: LexerInput> LexerDriver<I> {
+    fn new(input: I) -> Self {
+        Self {
+            input,
+            start_pos: 0,
+            start_line_column: LexerLineColumn { line: 1, column: 1 },
+            current_pos: 0,
+            current_token: None,
+            reached_end: false,
+            finished: false,
+            tab_width: 1,
+            column_unit: LexerColumnUnit::Char,
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Terminal, LexerError> {
+        let token = self.peek_token()?.clone();
+        self.move_start_pos();
+        self.current_token = None;
+        Ok(token)
+    }
+
+    fn peek_token(&mut self) -> Result<&Terminal, LexerError> {
+        if self.current_token.is_none() {
+            self.skip_separators()?;
+            if self.peek_char()?.is_none() {
+                let end_token = Terminal::new(TerminalClass::End, self.current_span());
+                self.current_token = Some(end_token);
+                self.reached_end = true;
+            } else {
+                self.current_token = Some(self.get()?);
+            }
+        }
+        Ok(self.current_token.as_ref().unwrap())
+    }
+
+    /// Obtains the line and column following the character `c` at `line_column`.
+    fn advance(&self, line_column: LexerLineColumn, c: char) -> LexerLineColumn {
+        let LexerLineColumn { line, column } = line_column;
+        let column = match (c, self.column_unit) {
+            ('\n', _) => return LexerLineColumn { line: line + 1, column: 1 },
+            ('\t', LexerColumnUnit::Char) => {
+                (column - 1) / self.tab_width * self.tab_width + self.tab_width + 1
+            }
+            (_, LexerColumnUnit::Char) => column + 1,
+            (_, LexerColumnUnit::Utf16) => column + c.len_utf16(),
+        };
+        LexerLineColumn { line, column }
+    }
+
+    /// Obtains the line and column of `pos`, which must not be before the current token.
+    fn line_column(&self, pos: usize) -> LexerLineColumn {
+        self.input
+            .slice(self.start_pos, pos)
+            .chars()
+            .fold(self.start_line_column, |line_column, c| {
+                self.advance(line_column, c)
+            })
+    }
+
+    fn move_start_pos(&mut self) {
+        self.start_line_column = self.line_column(self.current_pos);
+        self.start_pos = self.current_pos;
+    }
+
+    fn get(&mut self) -> Result<Terminal, LexerError> {
+        match self.longest_match()? {
+            Some((class, end_pos)) => {
+                self.current_pos = end_pos;
+                Ok(Terminal::new(class, self.current_span()))
+            }
+            None => {
+                let found = self.peek_char()?.unwrap();
+                Err(self.error(LexerErrorKind::UnexpectedChar(found)))
+            }
+        }
+    }
+
+    fn error(&self, kind: LexerErrorKind) -> LexerError {
+        let end_pos = match kind {
+            LexerErrorKind::UnexpectedChar(c) => self.current_pos + c.len_utf8(),
+            _ => self.current_pos,
+        };
+        let LexerLineColumn { line, column } = self.line_column(self.current_pos);
+        LexerError {
+            kind,
+            start_pos: self.current_pos,
+            end_pos,
+            line,
+            column,
+        }
+    }
+
+    fn peek_char(&mut self) -> Result<Option<char>, LexerError> {
+        self.input
+            .char_at(self.current_pos, self.start_pos)
+            .map_err(|kind| self.error(kind))
+    }
+
+    fn read_char(&mut self) -> Result<Option<char>, LexerError> {
+        let ch = self.peek_char()?;
+        if let Some(ch) = ch {
+            self.current_pos += ch.len_utf8();
+        }
+        Ok(ch)
+    }
+
+    fn skip_separators(&mut self) -> Result<(), LexerError> {
+        while let Some(c) = self.peek_char()? {
+            if Lexer::is_separator(c) {
+                self.read_char()?;
+            } else {
+                break;
+            }
+        }
+        self.move_start_pos();
+        Ok(())
+    }
+
+    fn current_span(&self) -> Span {
+        Span::new(self.start_pos, self.current_pos)
+    }
+
+    /// Obtains the class and the end of the longest token at `current_pos`, which is restored.
+    fn longest_match(&mut self) -> Result<Option<(TerminalClass, usize)>, LexerError> {
+        let pos = self.current_pos;
+        let mut longest_match = None;
+        let mut state = 0;
+        loop {
+            state = match state {
+                0 => match self.read_char()? {
+                    Some('\'') => 1,
+                    Some('*') => 2,
+                    Some('-') => 3,
+                    Some('0'..='9') => 4,
+                    Some('A'..='Z' | 'c'..='h' | 'j'..='z') => 5,
+                    Some('a') => 6,
+                    Some('b') => 7,
+                    Some('i') => 8,
+                    Some('α'..='γ') => 9,
+                    _ => break,
+                },
+                1 => match self.read_char()? {
+                    Some('\'') => 10,
+                    Some('a'..='z') => 1,
+                    _ => break,
+                },
+                2 => {
+                    longest_match = Some((TerminalClass::Star, self.current_pos));
+                    break;
+                }
+                3 => {
+                    longest_match = Some((TerminalClass::Arrow, self.current_pos));
+                    match self.read_char()? {
+                        Some('>') => 11,
+                        _ => break,
+                    }
+                }
+                4 => {
+                    longest_match = Some((TerminalClass::Number, self.current_pos));
+                    match self.read_char()? {
+                        Some('0'..='9') => 4,
+                        _ => break,
+                    }
+                }
+                5 => {
+                    longest_match = Some((TerminalClass::Identifier, self.current_pos));
+                    match self.read_char()? {
+                        Some('0'..='9' | 'A'..='Z' | 'a'..='z') => 5,
+                        _ => break,
+                    }
+                }
+                6 => {
+                    longest_match = Some((TerminalClass::Identifier, self.current_pos));
+                    match self.read_char()? {
+                        Some('0'..='9' | 'A'..='Z' | 'a'..='z') => 5,
+                        _ => break,
+                    }
+                }
+                7 => {
+                    longest_match = Some((TerminalClass::Identifier, self.current_pos));
+                    match self.read_char()? {
+                        Some('0'..='9' | 'A'..='Z' | 'a'..='b' | 'd'..='z') => 5,
+                        Some('c') => 6,
+                        _ => break,
+                    }
+                }
+                8 => {
+                    longest_match = Some((TerminalClass::Identifier, self.current_pos));
+                    match self.read_char()? {
+                        Some('0'..='9' | 'A'..='Z' | 'a'..='e' | 'g'..='z') => 5,
+                        Some('f') => 12,
+                        _ => break,
+                    }
+                }
+                9 => {
+                    longest_match = Some((TerminalClass::Greek, self.current_pos));
+                    match self.read_char()? {
+                        Some('α'..='γ') => 9,
+                        _ => break,
+                    }
+                }
+                10 => {
+                    longest_match = Some((TerminalClass::Quote, self.current_pos));
+                    break;
+                }
+                11 => {
+                    longest_match = Some((TerminalClass::Arrow, self.current_pos));
+                    break;
+                }
+                12 => {
+                    longest_match = Some((TerminalClass::If, self.current_pos));
+                    match self.read_char()? {
+                        Some('0'..='9' | 'A'..='Z' | 'a'..='z') => 5,
+                        _ => break,
+                    }
+                }
+                _ => unreachable!(),
+            };
+        }
+        self.current_pos = pos;
+        Ok(longest_match)
+    }
+}
+
+impl<I: LexerInput> Iterator for LexerDriver<I> {
+    type Item = Result<Terminal, LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let token = self.next_token();
+        self.finished = self.reached_end || token.is_err();
+        Some(token)
+    }
+}
+
+pub struct LexerStream<R> {
+    driver: LexerDriver<LexerReader<R>>,
+}
+
+impl<R: std::io::BufRead> LexerStream<R> {
+    pub fn new(reader: R) -> Self {
+        let reader = LexerReader {
+            reader,
+            buffer: vec![],
+            buffer_start_pos: 0,
+            reached_eof: false,
+        };
+        Self {
+            driver: LexerDriver::new(reader),
+        }
+    }
+
+    pub fn with_tab_width(mut self, tab_width: usize) -> Self {
+        self.driver.tab_width = tab_width.max(1);
+        self
+    }
+
+    pub fn with_column_unit(mut self, column_unit: LexerColumnUnit) -> Self {
+        self.driver.column_unit = column_unit;
+        self
+    }
+
+    pub fn next_token(&mut self) -> Result<Terminal, LexerError> {
+        self.driver.next_token()
+    }
+
+    pub fn peek_token(&mut self) -> Result<&Terminal, LexerError> {
+        self.driver.peek_token()
+    }
+
+    pub fn tokenize_all(&mut self) -> Result<Vec<Terminal>, LexerError> {
+        self.by_ref().collect()
+    }
+
+    pub fn get_lexeme(&self, token: &Terminal) -> Option<&str> {
+        let span = token.span();
+        let reader = &self.driver.input;
+        let start_idx = span.start_pos().checked_sub(reader.buffer_start_pos)?;
+        let end_idx = span.end_pos() - reader.buffer_start_pos;
+        str::from_utf8(reader.buffer.get(start_idx..end_idx)?).ok()
+    }
+}
+
+impl<R: std::io::BufRead> Iterator for LexerStream<R> {
+    type Item = Result<Terminal, LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.driver.next()
+    }
+}
+
+/// Input read from a `std::io::BufRead`, of which only the current token is kept.
+struct LexerReader<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    buffer_start_pos: usize,
+    reached_eof: bool,
+}
+
+impl<R: std::io::BufRead> LexerReader<R> {
+    fn fill_buffer(&mut self, start_pos: usize) -> Result<(), std::io::Error> {
+        // Bytes before the current token are never backtracked to, hence they can be discarded.
+        _ = self.buffer.drain(..start_pos - self.buffer_start_pos);
+        self.buffer_start_pos = start_pos;
+        let chunk = self.reader.fill_buf()?;
+        if chunk.is_empty() {
+            self.reached_eof = true;
+        }
+        self.buffer.extend_from_slice(chunk);
+        let chunk_length = chunk.len();
+        self.reader.consume(chunk_length);
+        Ok(())
+    }
+}
+
+impl<R: std::io::BufRead> LexerInput for LexerReader<R> {
+    fn char_at(&mut self, pos: usize, start_pos: usize) -> Result<Option<char>, LexerErrorKind> {
+        let mut idx = pos - self.buffer_start_pos;
+        while self.buffer.len() < idx + 4 && !self.reached_eof {
+            self.fill_buffer(start_pos).map_err(LexerErrorKind::Io)?;
+            idx = pos - self.buffer_start_pos;
+        }
+        let bytes = &self.buffer[idx..self.buffer.len().min(idx + 4)];
+        let valid_bytes = match str::from_utf8(bytes) {
+            Ok(valid_bytes) => valid_bytes,
+            Err(error) => str::from_utf8(&bytes[..error.valid_up_to()]).unwrap(),
+        };
+        match valid_bytes.chars().next() {
+            Some(c) => Ok(Some(c)),
+            None if bytes.is_empty() => Ok(None),
+            None => Err(LexerErrorKind::InvalidUtf8),
+        }
+    }
+
+    fn slice(&self, start_pos: usize, end_pos: usize) -> &str {
+        let buffer_start_pos = self.buffer_start_pos;
+        let bytes = &self.buffer[start_pos - buffer_start_pos..end_pos - buffer_start_pos];
+        str::from_utf8(bytes).unwrap()
+    }
+}
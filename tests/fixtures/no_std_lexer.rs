@@ -0,0 +1,584 @@
+// @generated by JLEK 0.1.3, fingerprint 1b9a4283870c43ba. Do not edit.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TerminalClass {
+    If,
+    Number,
+    Identifier,
+    Star,
+    Arrow,
+    Quote,
+    Alternation,
+    Greek,
+    End,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Span {
+    start_pos: usize,
+    end_pos: usize,
+}
+
+impl Span {
+    pub fn new(start_pos: usize, end_pos: usize) -> Self {
+        Self { start_pos, end_pos }
+    }
+
+    pub fn start_pos(&self) -> usize {
+        self.start_pos
+    }
+
+    pub fn end_pos(&self) -> usize {
+        self.end_pos
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Terminal {
+    class: TerminalClass,
+    span: Span,
+}
+
+impl Terminal {
+    pub fn new(class: TerminalClass, span: Span) -> Self {
+        Self { class, span }
+    }
+
+    pub fn class(&self) -> TerminalClass {
+        self.class
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+}
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LexerLineColumn {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LexerColumnUnit {
+    Char,
+    Utf16,
+}
+
+#[derive(Debug)]
+pub struct LexerError {
+    kind: LexerErrorKind,
+    start_pos: usize,
+    end_pos: usize,
+    line: usize,
+    column: usize,
+}
+
+#[derive(Debug)]
+pub enum LexerErrorKind {
+    UnexpectedChar(char),
+    InvalidUtf8,
+}
+
+impl LexerError {
+    pub fn kind(&self) -> &LexerErrorKind {
+        &self.kind
+    }
+
+    pub fn span(&self) -> Span {
+        Span::new(self.start_pos, self.end_pos)
+    }
+
+    pub fn found(&self) -> Option<char> {
+        match self.kind {
+            LexerErrorKind::UnexpectedChar(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    pub fn byte_pos(&self) -> usize {
+        self.start_pos
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl core::fmt::Display for LexerError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match &self.kind {
+            LexerErrorKind::UnexpectedChar(c) => write!(f, "unexpected character {c:?}")?,
+            LexerErrorKind::InvalidUtf8 => write!(f, "invalid UTF-8")?,
+        }
+        write!(f, " at line {}, column {}", self.line, self.column)
+    }
+}
+
+impl core::error::Error for LexerError {}
+
+#[derive(Copy, Clone)]
+struct LexerState {
+    class: Option<TerminalClass>,
+}
+
+pub struct Lexer<'src> {
+    source: &'src str,
+    driver: LexerDriver<&'src str>,
+    line_start_indices: alloc::vec::Vec<usize>,
+    context_lines: usize,
+    color: bool,
+}
+
+impl<'src> Lexer<'src> {
+    const STATES: &'static [LexerState] = &[
+        LexerState { class: None },
+        LexerState { class: None },
+        LexerState { class: Some(TerminalClass::If) },
+        LexerState { class: None },
+        LexerState { class: Some(TerminalClass::Number) },
+        LexerState { class: None },
+        LexerState { class: Some(TerminalClass::Identifier) },
+        LexerState { class: None },
+        LexerState { class: Some(TerminalClass::Star) },
+        LexerState { class: None },
+        LexerState { class: Some(TerminalClass::Arrow) },
+        LexerState { class: Some(TerminalClass::Arrow) },
+        LexerState { class: None },
+        LexerState { class: None },
+        LexerState { class: Some(TerminalClass::Quote) },
+        LexerState { class: None },
+        LexerState { class: Some(TerminalClass::Alternation) },
+        LexerState { class: None },
+        LexerState { class: None },
+        LexerState { class: Some(TerminalClass::Greek) },
+    ];
+    const INITIAL_STATES: &'static [usize] = &[0, 3, 5, 7, 9, 12, 15, 18];
+    const NUM_OF_STATES: usize = 20;
+    const ASCII_CLASSES: &'static [usize; 128] = &[
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 2, 0, 0, 3, 0, 0,
+        4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 0, 0, 0, 0, 5, 0,
+        0, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6,
+        6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 0, 0, 0, 0, 0,
+        0, 7, 8, 9, 10, 10, 11, 10, 10, 12, 10, 10, 10, 10, 10, 10,
+        10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 0, 0, 0, 0, 0,
+    ];
+    const CHAR_CLASSES: &'static [(char, char, usize)] = &[
+        ('\'', '\'', 1),
+        ('*', '*', 2),
+        ('-', '-', 3),
+        ('0', '9', 4),
+        ('>', '>', 5),
+        ('A', 'Z', 6),
+        ('a', 'a', 7),
+        ('b', 'b', 8),
+        ('c', 'c', 9),
+        ('d', 'e', 10),
+        ('f', 'f', 11),
+        ('g', 'h', 10),
+        ('i', 'i', 12),
+        ('j', 'z', 10),
+        ('α', 'γ', 13),
+    ];
+    const TRANSITION_TABLE: &'static [[usize; 14]] = &[
+        [20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 1, 20],
+        [20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 2, 20, 20],
+        [20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 20, 20, 20, 4, 20, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 20, 20, 20, 4, 20, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 20, 20, 20, 20, 20, 6, 6, 6, 6, 6, 6, 6, 20],
+        [20, 20, 20, 20, 6, 20, 6, 6, 6, 6, 6, 6, 6, 20],
+        [20, 20, 8, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 20, 20, 10, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 20, 20, 20, 20, 11, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 13, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 14, 20, 20, 20, 20, 20, 13, 13, 13, 13, 13, 13, 20],
+        [20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 20, 20, 20, 20, 20, 20, 16, 17, 20, 20, 20, 20, 20],
+        [20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 20, 20, 20, 20, 20, 20, 20, 20, 16, 20, 20, 20, 20],
+        [20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 19],
+        [20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 19],
+    ];
+
+    pub fn from_source_str(source: &'src str) -> Self {
+        let mut line_start_indices = source
+            .bytes()
+            .enumerate()
+            .filter_map(|(i, c)| if c == b'\n' { Some(i + 1) } else { None })
+            .collect::<alloc::vec::Vec<usize>>();
+        line_start_indices.insert(0, 0);
+        Self {
+            source,
+            driver: LexerDriver::new(source),
+            line_start_indices,
+            context_lines: 0,
+            color: false,
+        }
+    }
+
+    pub fn with_tab_width(mut self, tab_width: usize) -> Self {
+        self.driver.tab_width = tab_width.max(1);
+        self
+    }
+
+    pub fn with_column_unit(mut self, column_unit: LexerColumnUnit) -> Self {
+        self.driver.column_unit = column_unit;
+        self
+    }
+
+    pub fn with_context_lines(mut self, context_lines: usize) -> Self {
+        self.context_lines = context_lines;
+        self
+    }
+
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn next_token(&mut self) -> Result<Terminal, LexerError> {
+        self.driver.next_token()
+    }
+
+    pub fn peek_token(&mut self) -> Result<&Terminal, LexerError> {
+        self.driver.peek_token()
+    }
+
+    pub fn tokenize_all(&mut self) -> Result<alloc::vec::Vec<Terminal>, LexerError> {
+        self.by_ref().collect()
+    }
+
+    pub fn get_lexeme(&self, token: &Terminal) -> &'src str {
+        self.str_from_span(token.span())
+    }
+
+    pub fn str_from_span(&self, span: &Span) -> &'src str {
+        &self.source[span.start_pos()..span.end_pos()]
+    }
+
+    pub fn line_column(&self, pos: usize) -> LexerLineColumn {
+        let line = self.line_start_indices.partition_point(|&i| i <= pos);
+        let line_start_idx = self.line_start_indices[line - 1];
+        self.source[line_start_idx..pos]
+            .chars()
+            .fold(LexerLineColumn { line, column: 1 }, |line_column, c| {
+                self.driver.advance(line_column, c)
+            })
+    }
+
+    pub fn span_line_column(&self, span: &Span) -> (LexerLineColumn, LexerLineColumn) {
+        (
+            self.line_column(span.start_pos()),
+            self.line_column(span.end_pos()),
+        )
+    }
+
+    pub fn show_span(&self, span: &Span) -> alloc::string::String {
+        let (blue, red, end) = self.colors();
+        let first_line = self
+            .line_start_indices
+            .partition_point(|&i| i <= span.start_pos());
+        let last_line = self
+            .line_start_indices
+            .partition_point(|&i| i < span.end_pos())
+            .max(first_line);
+        let context_first_line = first_line.saturating_sub(self.context_lines).max(1);
+        let context_last_line =
+            (last_line + self.context_lines).min(self.line_start_indices.len());
+        let mut rendered_lines = alloc::vec![];
+        for line_number in context_first_line..=context_last_line {
+            let (line_start_idx, line) = self.line(line_number);
+            rendered_lines.push(alloc::format!("{blue}Line {line_number:3}|{end}{line}"));
+            if line_number != first_line && line_number != last_line {
+                continue;
+            }
+            let line_end_idx = line_start_idx + line.len();
+            let marker_start =
+                span.start_pos().clamp(line_start_idx, line_end_idx) - line_start_idx;
+            let marker_end = span.end_pos().clamp(line_start_idx, line_end_idx) - line_start_idx;
+            // Tabs are kept in the indentation, so that the marker lines up with the line.
+            let indent = line[..marker_start]
+                .chars()
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect::<alloc::string::String>();
+            let marked_length = line[marker_start..marker_end].chars().count();
+            let dashes = "-".repeat(marked_length.saturating_sub(1));
+            let marker = if line_number == first_line {
+                alloc::format!("^{dashes}")
+            } else {
+                alloc::format!("{dashes}^")
+            };
+            rendered_lines.push(alloc::format!("         {red}{indent}{marker}{end}"));
+        }
+        rendered_lines.join("\n")
+    }
+
+    pub fn show_error(&self, error: &LexerError) -> alloc::string::String {
+        let (_, red, end) = self.colors();
+        let span_str = self.show_span(&error.span());
+        alloc::format!("{span_str}\n{red}error{end}: {error}")
+    }
+
+    fn line(&self, line_number: usize) -> (usize, &'src str) {
+        let line_start_idx = self.line_start_indices[line_number - 1];
+        let line_end_idx = match self.line_start_indices.get(line_number) {
+            Some(idx) => idx - 1,
+            None => self.source.len(),
+        };
+        let line = self.source[line_start_idx..line_end_idx].trim_end_matches('\r');
+        (line_start_idx, line)
+    }
+
+    fn colors(&self) -> (&'static str, &'static str, &'static str) {
+        if self.color {
+            ("\x1B[34m", "\x1B[31m", "\x1B[0m")
+        } else {
+            ("", "", "")
+        }
+    }
+
+    fn is_separator(c: char) -> bool {
+        c.is_whitespace()
+    }
+
+    fn next_state(state: usize, input: char) -> Option<usize> {
+        let next = Self::TRANSITION_TABLE[state][Self::char_class(input)];
+        (next < Self::NUM_OF_STATES).then_some(next)
+    }
+
+    fn char_class(input: char) -> usize {
+        if input.is_ascii() {
+            return Self::ASCII_CLASSES[input as usize];
+        }
+        let idx = Self::CHAR_CLASSES.partition_point(|&(_, end, _)| end < input);
+        match Self::CHAR_CLASSES.get(idx) {
+            Some(&(start, _, class)) if start <= input => class,
+            _ => 0,
+        }
+    }
+}
+
+impl<'src> Iterator for Lexer<'src> {
+    type Item = Result<Terminal, LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.driver.next()
+    }
+}
+
+/// Input of a `LexerDriver`, indexed by byte offsets.
+trait LexerInput {
+    /// Obtains the character at `pos`. The input before `start_pos`, the start of the current
+    /// token, is no longer needed.
+    fn char_at(&mut self, pos: usize, start_pos: usize) -> Result<Option<char>, LexerErrorKind>;
+
+    /// Obtains the input from `start_pos` to `end_pos`, which must have been read and must not be
+    /// before the current token.
+    fn slice(&self, start_pos: usize, end_pos: usize) -> &str;
+}
+
+impl LexerInput for &str {
+    fn char_at(&mut self, pos: usize, _: usize) -> Result<Option<char>, LexerErrorKind> {
+        Ok(self[pos..].chars().next())
+    }
+
+    fn slice(&self, start_pos: usize, end_pos: usize) -> &str {
+        &self[start_pos..end_pos]
+    }
+}
+
+/// Lexes the tokens of an input, for both `Lexer` and its streaming counterpart.
+struct LexerDriver<I> {
+    input: I,
+    start_pos: usize,
+    start_line_column: LexerLineColumn,
+    current_pos: usize,
+    current_token: Option<Terminal>,
+    // The states of the token DFAs after the characters read so far, and a buffer for the next
+    // ones, kept to avoid an allocation per character.
+    states: alloc::vec::Vec<usize>,
+    next_states: alloc::vec::Vec<usize>,
+    reached_end: bool,
+    finished: bool,
+    tab_width: usize,
+    column_unit: LexerColumnUnit,
+}
+
+impl<I: LexerInput> LexerDriver<I> {
+    fn new(input: I) -> Self {
+        Self {
+            input,
+            start_pos: 0,
+            start_line_column: LexerLineColumn { line: 1, column: 1 },
+            current_pos: 0,
+            current_token: None,
+            states: alloc::vec::Vec::new(),
+            next_states: alloc::vec::Vec::new(),
+            reached_end: false,
+            finished: false,
+            tab_width: 1,
+            column_unit: LexerColumnUnit::Char,
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Terminal, LexerError> {
+        let token = self.peek_token()?.clone();
+        self.move_start_pos();
+        self.current_token = None;
+        Ok(token)
+    }
+
+    fn peek_token(&mut self) -> Result<&Terminal, LexerError> {
+        if self.current_token.is_none() {
+            self.skip_separators()?;
+            if self.peek_char()?.is_none() {
+                let end_token = Terminal::new(TerminalClass::End, self.current_span());
+                self.current_token = Some(end_token);
+                self.reached_end = true;
+            } else {
+                self.current_token = Some(self.get()?);
+            }
+        }
+        Ok(self.current_token.as_ref().unwrap())
+    }
+
+    /// Obtains the line and column following the character `c` at `line_column`.
+    fn advance(&self, line_column: LexerLineColumn, c: char) -> LexerLineColumn {
+        let LexerLineColumn { line, column } = line_column;
+        let column = match (c, self.column_unit) {
+            ('\n', _) => return LexerLineColumn { line: line + 1, column: 1 },
+            ('\t', LexerColumnUnit::Char) => {
+                (column - 1) / self.tab_width * self.tab_width + self.tab_width + 1
+            }
+            (_, LexerColumnUnit::Char) => column + 1,
+            (_, LexerColumnUnit::Utf16) => column + c.len_utf16(),
+        };
+        LexerLineColumn { line, column }
+    }
+
+    /// Obtains the line and column of `pos`, which must not be before the current token.
+    fn line_column(&self, pos: usize) -> LexerLineColumn {
+        self.input
+            .slice(self.start_pos, pos)
+            .chars()
+            .fold(self.start_line_column, |line_column, c| {
+                self.advance(line_column, c)
+            })
+    }
+
+    fn move_start_pos(&mut self) {
+        self.start_line_column = self.line_column(self.current_pos);
+        self.start_pos = self.current_pos;
+    }
+
+    fn get(&mut self) -> Result<Terminal, LexerError> {
+        match self.longest_match()? {
+            Some((class, end_pos)) => {
+                self.current_pos = end_pos;
+                Ok(Terminal::new(class, self.current_span()))
+            }
+            None => {
+                let found = self.peek_char()?.unwrap();
+                Err(self.error(LexerErrorKind::UnexpectedChar(found)))
+            }
+        }
+    }
+
+    fn error(&self, kind: LexerErrorKind) -> LexerError {
+        let end_pos = match kind {
+            LexerErrorKind::UnexpectedChar(c) => self.current_pos + c.len_utf8(),
+            _ => self.current_pos,
+        };
+        let LexerLineColumn { line, column } = self.line_column(self.current_pos);
+        LexerError {
+            kind,
+            start_pos: self.current_pos,
+            end_pos,
+            line,
+            column,
+        }
+    }
+
+    fn peek_char(&mut self) -> Result<Option<char>, LexerError> {
+        self.input
+            .char_at(self.current_pos, self.start_pos)
+            .map_err(|kind| self.error(kind))
+    }
+
+    fn read_char(&mut self) -> Result<Option<char>, LexerError> {
+        let ch = self.peek_char()?;
+        if let Some(ch) = ch {
+            self.current_pos += ch.len_utf8();
+        }
+        Ok(ch)
+    }
+
+    fn skip_separators(&mut self) -> Result<(), LexerError> {
+        while let Some(c) = self.peek_char()? {
+            if Lexer::is_separator(c) {
+                self.read_char()?;
+            } else {
+                break;
+            }
+        }
+        self.move_start_pos();
+        Ok(())
+    }
+
+    fn current_span(&self) -> Span {
+        Span::new(self.start_pos, self.current_pos)
+    }
+
+    /// Obtains the class and the end of the longest token at `current_pos`, which is restored.
+    fn longest_match(&mut self) -> Result<Option<(TerminalClass, usize)>, LexerError> {
+        let pos = self.current_pos;
+        let mut longest_match = None;
+        self.states.clear();
+        self.states.extend_from_slice(Lexer::INITIAL_STATES);
+        while let Some(c) = self.read_char()? {
+            self.next_states.clear();
+            for &state in &self.states {
+                if let Some(next_state) = Lexer::next_state(state, c) {
+                    self.next_states.push(next_state);
+                }
+            }
+            if self.next_states.is_empty() {
+                break;
+            }
+            core::mem::swap(&mut self.states, &mut self.next_states);
+            let prioritized_class = self
+                .states
+                .iter()
+                .filter_map(|state| Lexer::STATES[*state].class)
+                .min();
+            if let Some(class) = prioritized_class {
+                longest_match = Some((class, self.current_pos));
+            }
+        }
+        self.current_pos = pos;
+        Ok(longest_match)
+    }
+}
+
+impl<I: LexerInput> Iterator for LexerDriver<I> {
+    type Item = Result<Terminal, LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let token = self.next_token();
+        self.finished = self.reached_end || token.is_err();
+        Some(token)
+    }
+}
@@ -0,0 +1,649 @@
+// @generated by JLEK 0.1.3, fingerprint 0c9099a4f705472a. Do not edit.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QueryLexerLineColumn {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QueryLexerColumnUnit {
+    Char,
+    Utf16,
+}
+
+#[derive(Debug)]
+pub struct QueryLexerError {
+    kind: QueryLexerErrorKind,
+    start_pos: usize,
+    end_pos: usize,
+    line: usize,
+    column: usize,
+}
+
+#[derive(Debug)]
+pub enum QueryLexerErrorKind {
+    UnexpectedChar(char),
+    InvalidUtf8,
+    Io(std::io::Error),
+}
+
+impl QueryLexerError {
+    pub fn kind(&self) -> &QueryLexerErrorKind {
+        &self.kind
+    }
+
+    pub fn span(&self) -> crate::syntax::Location {
+        crate::syntax::Location::new(self.start_pos, self.end_pos)
+    }
+
+    pub fn found(&self) -> Option<char> {
+        match self.kind {
+            QueryLexerErrorKind::UnexpectedChar(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    pub fn byte_pos(&self) -> usize {
+        self.start_pos
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl std::fmt::Display for QueryLexerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            QueryLexerErrorKind::UnexpectedChar(c) => write!(f, "unexpected character {c:?}")?,
+            QueryLexerErrorKind::InvalidUtf8 => write!(f, "invalid UTF-8")?,
+            QueryLexerErrorKind::Io(error) => write!(f, "{error}")?,
+        }
+        write!(f, " at line {}, column {}", self.line, self.column)
+    }
+}
+
+impl std::error::Error for QueryLexerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            QueryLexerErrorKind::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+struct QueryLexerState {
+    class: Option<crate::syntax::TokenKind>,
+}
+
+pub struct QueryLexer<'src> {
+    source: &'src str,
+    driver: QueryLexerDriver<&'src str>,
+    line_start_indices: Vec<usize>,
+    context_lines: usize,
+    color: bool,
+}
+
+impl<'src> QueryLexer<'src> {
+    const STATES: &'static [QueryLexerState] = &[
+        QueryLexerState { class: None },
+        QueryLexerState { class: None },
+        QueryLexerState { class: Some(crate::syntax::TokenKind::If) },
+        QueryLexerState { class: None },
+        QueryLexerState { class: Some(crate::syntax::TokenKind::Number) },
+        QueryLexerState { class: None },
+        QueryLexerState { class: Some(crate::syntax::TokenKind::Identifier) },
+        QueryLexerState { class: None },
+        QueryLexerState { class: Some(crate::syntax::TokenKind::Star) },
+        QueryLexerState { class: None },
+        QueryLexerState { class: Some(crate::syntax::TokenKind::Arrow) },
+        QueryLexerState { class: Some(crate::syntax::TokenKind::Arrow) },
+        QueryLexerState { class: None },
+        QueryLexerState { class: None },
+        QueryLexerState { class: Some(crate::syntax::TokenKind::Quote) },
+        QueryLexerState { class: None },
+        QueryLexerState { class: Some(crate::syntax::TokenKind::Alternation) },
+        QueryLexerState { class: None },
+        QueryLexerState { class: None },
+        QueryLexerState { class: Some(crate::syntax::TokenKind::Greek) },
+    ];
+    const INITIAL_STATES: &'static [usize] = &[0, 3, 5, 7, 9, 12, 15, 18];
+    const NUM_OF_STATES: usize = 20;
+    const ASCII_CLASSES: &'static [usize; 128] = &[
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 2, 0, 0, 3, 0, 0,
+        4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 0, 0, 0, 0, 5, 0,
+        0, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6,
+        6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 0, 0, 0, 0, 0,
+        0, 7, 8, 9, 10, 10, 11, 10, 10, 12, 10, 10, 10, 10, 10, 10,
+        10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 0, 0, 0, 0, 0,
+    ];
+    const CHAR_CLASSES: &'static [(char, char, usize)] = &[
+        ('\'', '\'', 1),
+        ('*', '*', 2),
+        ('-', '-', 3),
+        ('0', '9', 4),
+        ('>', '>', 5),
+        ('A', 'Z', 6),
+        ('a', 'a', 7),
+        ('b', 'b', 8),
+        ('c', 'c', 9),
+        ('d', 'e', 10),
+        ('f', 'f', 11),
+        ('g', 'h', 10),
+        ('i', 'i', 12),
+        ('j', 'z', 10),
+        ('α', 'γ', 13),
+    ];
+    const TRANSITION_TABLE: &'static [[usize; 14]] = &[
+        [20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 1, 20],
+        [20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 2, 20, 20],
+        [20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 20, 20, 20, 4, 20, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 20, 20, 20, 4, 20, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 20, 20, 20, 20, 20, 6, 6, 6, 6, 6, 6, 6, 20],
+        [20, 20, 20, 20, 6, 20, 6, 6, 6, 6, 6, 6, 6, 20],
+        [20, 20, 8, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 20, 20, 10, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 20, 20, 20, 20, 11, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 13, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 14, 20, 20, 20, 20, 20, 13, 13, 13, 13, 13, 13, 20],
+        [20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 20, 20, 20, 20, 20, 20, 16, 17, 20, 20, 20, 20, 20],
+        [20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20],
+        [20, 20, 20, 20, 20, 20, 20, 20, 20, 16, 20, 20, 20, 20],
+        [20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 19],
+        [20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 19],
+    ];
+
+    pub fn from_source_str(source: &'src str) -> Self {
+        let mut line_start_indices = source
+            .bytes()
+            .enumerate()
+            .filter_map(|(i, c)| if c == b'\n' { Some(i + 1) } else { None })
+            .collect::<Vec<usize>>();
+        line_start_indices.insert(0, 0);
+        Self {
+            source,
+            driver: QueryLexerDriver::new(source),
+            line_start_indices,
+            context_lines: 0,
+            color: std::env::var_os("NO_COLOR").is_none_or(|no_color| no_color.is_empty()),
+        }
+    }
+
+    pub fn read_source(source_file: &std::path::Path) -> Result<String, std::io::Error> {
+        std::fs::read_to_string(source_file)
+    }
+
+    pub fn with_tab_width(mut self, tab_width: usize) -> Self {
+        self.driver.tab_width = tab_width.max(1);
+        self
+    }
+
+    pub fn with_column_unit(mut self, column_unit: QueryLexerColumnUnit) -> Self {
+        self.driver.column_unit = column_unit;
+        self
+    }
+
+    pub fn with_context_lines(mut self, context_lines: usize) -> Self {
+        self.context_lines = context_lines;
+        self
+    }
+
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn next_token(&mut self) -> Result<crate::syntax::Token, QueryLexerError> {
+        self.driver.next_token()
+    }
+
+    pub fn peek_token(&mut self) -> Result<&crate::syntax::Token, QueryLexerError> {
+        self.driver.peek_token()
+    }
+
+    pub fn tokenize_all(&mut self) -> Result<Vec<crate::syntax::Token>, QueryLexerError> {
+        self.by_ref().collect()
+    }
+
+    pub fn get_lexeme(&self, token: &crate::syntax::Token) -> &'src str {
+        self.str_from_span(token.span())
+    }
+
+    pub fn str_from_span(&self, span: &crate::syntax::Location) -> &'src str {
+        &self.source[span.start_pos()..span.end_pos()]
+    }
+
+    pub fn line_column(&self, pos: usize) -> QueryLexerLineColumn {
+        let line = self.line_start_indices.partition_point(|&i| i <= pos);
+        let line_start_idx = self.line_start_indices[line - 1];
+        self.source[line_start_idx..pos]
+            .chars()
+            .fold(QueryLexerLineColumn { line, column: 1 }, |line_column, c| {
+                self.driver.advance(line_column, c)
+            })
+    }
+
+    pub fn span_line_column(&self, span: &crate::syntax::Location) -> (QueryLexerLineColumn, QueryLexerLineColumn) {
+        (
+            self.line_column(span.start_pos()),
+            self.line_column(span.end_pos()),
+        )
+    }
+
+    pub fn show_span(&self, span: &crate::syntax::Location) -> String {
+        let (blue, red, end) = self.colors();
+        let first_line = self
+            .line_start_indices
+            .partition_point(|&i| i <= span.start_pos());
+        let last_line = self
+            .line_start_indices
+            .partition_point(|&i| i < span.end_pos())
+            .max(first_line);
+        let context_first_line = first_line.saturating_sub(self.context_lines).max(1);
+        let context_last_line =
+            (last_line + self.context_lines).min(self.line_start_indices.len());
+        let mut rendered_lines = vec![];
+        for line_number in context_first_line..=context_last_line {
+            let (line_start_idx, line) = self.line(line_number);
+            rendered_lines.push(format!("{blue}Line {line_number:3}|{end}{line}"));
+            if line_number != first_line && line_number != last_line {
+                continue;
+            }
+            let line_end_idx = line_start_idx + line.len();
+            let marker_start =
+                span.start_pos().clamp(line_start_idx, line_end_idx) - line_start_idx;
+            let marker_end = span.end_pos().clamp(line_start_idx, line_end_idx) - line_start_idx;
+            // Tabs are kept in the indentation, so that the marker lines up with the line.
+            let indent = line[..marker_start]
+                .chars()
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect::<String>();
+            let marked_length = line[marker_start..marker_end].chars().count();
+            let dashes = "-".repeat(marked_length.saturating_sub(1));
+            let marker = if line_number == first_line {
+                format!("^{dashes}")
+            } else {
+                format!("{dashes}^")
+            };
+            rendered_lines.push(format!("         {red}{indent}{marker}{end}"));
+        }
+        rendered_lines.join("\n")
+    }
+
+    pub fn show_error(&self, error: &QueryLexerError) -> String {
+        let (_, red, end) = self.colors();
+        let span_str = self.show_span(&error.span());
+        format!("{span_str}\n{red}error{end}: {error}")
+    }
+
+    fn line(&self, line_number: usize) -> (usize, &'src str) {
+        let line_start_idx = self.line_start_indices[line_number - 1];
+        let line_end_idx = match self.line_start_indices.get(line_number) {
+            Some(idx) => idx - 1,
+            None => self.source.len(),
+        };
+        let line = self.source[line_start_idx..line_end_idx].trim_end_matches('\r');
+        (line_start_idx, line)
+    }
+
+    fn colors(&self) -> (&'static str, &'static str, &'static str) {
+        if self.color {
+            ("\x1B[34m", "\x1B[31m", "\x1B[0m")
+        } else {
+            ("", "", "")
+        }
+    }
+
+    fn is_separator(c: char) -> bool {
+        c.is_whitespace()
+    }
+
+    fn next_state(state: usize, input: char) -> Option<usize> {
+        let next = Self::TRANSITION_TABLE[state][Self::char_class(input)];
+        (next < Self::NUM_OF_STATES).then_some(next)
+    }
+
+    fn char_class(input: char) -> usize {
+        if input.is_ascii() {
+            return Self::ASCII_CLASSES[input as usize];
+        }
+        let idx = Self::CHAR_CLASSES.partition_point(|&(_, end, _)| end < input);
+        match Self::CHAR_CLASSES.get(idx) {
+            Some(&(start, _, class)) if start <= input => class,
+            _ => 0,
+        }
+    }
+}
+
+impl<'src> Iterator for QueryLexer<'src> {
+    type Item = Result<crate::syntax::Token, QueryLexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.driver.next()
+    }
+}
+
+/// Input of a `QueryLexerDriver`, indexed by byte offsets.
+trait QueryLexerInput {
+    /// Obtains the character at `pos`. The input before `start_pos`, the start of the current
+    /// token, is no longer needed.
+    fn char_at(&mut self, pos: usize, start_pos: usize) -> Result<Option<char>, QueryLexerErrorKind>;
+
+    /// Obtains the input from `start_pos` to `end_pos`, which must have been read and must not be
+    /// before the current token.
+    fn slice(&self, start_pos: usize, end_pos: usize) -> &str;
+}
+
+impl QueryLexerInput for &str {
+    fn char_at(&mut self, pos: usize, _: usize) -> Result<Option<char>, QueryLexerErrorKind> {
+        Ok(self[pos..].chars().next())
+    }
+
+    fn slice(&self, start_pos: usize, end_pos: usize) -> &str {
+        &self[start_pos..end_pos]
+    }
+}
+
+/// Lexes the tokens of an input, for both `QueryLexer` and its streaming counterpart.
+struct QueryLexerDriver<I> {
+    input: I,
+    start_pos: usize,
+    start_line_column: QueryLexerLineColumn,
+    current_pos: usize,
+    current_token: Option<crate::syntax::Token>,
+    // The states of the token DFAs after the characters read so far, and a buffer for the next
+    // ones, kept to avoid an allocation per character.
+    states: Vec<usize>,
+    next_states: Vec<usize>,
+    reached_end: bool,
+    finished: bool,
+    tab_width: usize,
+    column_unit: QueryLexerColumnUnit,
+}
+
+impl<I: QueryLexerInput> QueryLexerDriver<I> {
+    fn new(input: I) -> Self {
+        Self {
+            input,
+            start_pos: 0,
+            start_line_column: QueryLexerLineColumn { line: 1, column: 1 },
+            current_pos: 0,
+            current_token: None,
+            states: Vec::new(),
+            next_states: Vec::new(),
+            reached_end: false,
+            finished: false,
+            tab_width: 1,
+            column_unit: QueryLexerColumnUnit::Char,
+        }
+    }
+
+    fn next_token(&mut self) -> Result<crate::syntax::Token, QueryLexerError> {
+        let token = self.peek_token()?.clone();
+        self.move_start_pos();
+        self.current_token = None;
+        Ok(token)
+    }
+
+    fn peek_token(&mut self) -> Result<&crate::syntax::Token, QueryLexerError> {
+        if self.current_token.is_none() {
+            self.skip_separators()?;
+            if self.peek_char()?.is_none() {
+                let end_token = crate::syntax::Token::new(crate::syntax::TokenKind::End, self.current_span());
+                self.current_token = Some(end_token);
+                self.reached_end = true;
+            } else {
+                self.current_token = Some(self.get()?);
+            }
+        }
+        Ok(self.current_token.as_ref().unwrap())
+    }
+
+    /// Obtains the line and column following the character `c` at `line_column`.
+    fn advance(&self, line_column: QueryLexerLineColumn, c: char) -> QueryLexerLineColumn {
+        let QueryLexerLineColumn { line, column } = line_column;
+        let column = match (c, self.column_unit) {
+            ('\n', _) => return QueryLexerLineColumn { line: line + 1, column: 1 },
+            ('\t', QueryLexerColumnUnit::Char) => {
+                (column - 1) / self.tab_width * self.tab_width + self.tab_width + 1
+            }
+            (_, QueryLexerColumnUnit::Char) => column + 1,
+            (_, QueryLexerColumnUnit::Utf16) => column + c.len_utf16(),
+        };
+        QueryLexerLineColumn { line, column }
+    }
+
+    /// Obtains the line and column of `pos`, which must not be before the current token.
+    fn line_column(&self, pos: usize) -> QueryLexerLineColumn {
+        self.input
+            .slice(self.start_pos, pos)
+            .chars()
+            .fold(self.start_line_column, |line_column, c| {
+                self.advance(line_column, c)
+            })
+    }
+
+    fn move_start_pos(&mut self) {
+        self.start_line_column = self.line_column(self.current_pos);
+        self.start_pos = self.current_pos;
+    }
+
+    fn get(&mut self) -> Result<crate::syntax::Token, QueryLexerError> {
+        match self.longest_match()? {
+            Some((class, end_pos)) => {
+                self.current_pos = end_pos;
+                Ok(crate::syntax::Token::new(class, self.current_span()))
+            }
+            None => {
+                let found = self.peek_char()?.unwrap();
+                Err(self.error(QueryLexerErrorKind::UnexpectedChar(found)))
+            }
+        }
+    }
+
+    fn error(&self, kind: QueryLexerErrorKind) -> QueryLexerError {
+        let end_pos = match kind {
+            QueryLexerErrorKind::UnexpectedChar(c) => self.current_pos + c.len_utf8(),
+            _ => self.current_pos,
+        };
+        let QueryLexerLineColumn { line, column } = self.line_column(self.current_pos);
+        QueryLexerError {
+            kind,
+            start_pos: self.current_pos,
+            end_pos,
+            line,
+            column,
+        }
+    }
+
+    fn peek_char(&mut self) -> Result<Option<char>, QueryLexerError> {
+        self.input
+            .char_at(self.current_pos, self.start_pos)
+            .map_err(|kind| self.error(kind))
+    }
+
+    fn read_char(&mut self) -> Result<Option<char>, QueryLexerError> {
+        let ch = self.peek_char()?;
+        if let Some(ch) = ch {
+            self.current_pos += ch.len_utf8();
+        }
+        Ok(ch)
+    }
+
+    fn skip_separators(&mut self) -> Result<(), QueryLexerError> {
+        while let Some(c) = self.peek_char()? {
+            if QueryLexer::is_separator(c) {
+                self.read_char()?;
+            } else {
+                break;
+            }
+        }
+        self.move_start_pos();
+        Ok(())
+    }
+
+    fn current_span(&self) -> crate::syntax::Location {
+        crate::syntax::Location::new(self.start_pos, self.current_pos)
+    }
+
+    /// Obtains the class and the end of the longest token at `current_pos`, which is restored.
+    fn longest_match(&mut self) -> Result<Option<(crate::syntax::TokenKind, usize)>, QueryLexerError> {
+        let pos = self.current_pos;
+        let mut longest_match = None;
+        self.states.clear();
+        self.states.extend_from_slice(QueryLexer::INITIAL_STATES);
+        while let Some(c) = self.read_char()? {
+            self.next_states.clear();
+            for &state in &self.states {
+                if let Some(next_state) = QueryLexer::next_state(state, c) {
+                    self.next_states.push(next_state);
+                }
+            }
+            if self.next_states.is_empty() {
+                break;
+            }
+            core::mem::swap(&mut self.states, &mut self.next_states);
+            let prioritized_class = self
+                .states
+                .iter()
+                .filter_map(|state| QueryLexer::STATES[*state].class)
+                .min();
+            if let Some(class) = prioritized_class {
+                longest_match = Some((class, self.current_pos));
+            }
+        }
+        self.current_pos = pos;
+        Ok(longest_match)
+    }
+}
+
+impl<I: QueryLexerInput> Iterator for QueryLexerDriver<I> {
+    type Item = Result<crate::syntax::Token, QueryLexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let token = self.next_token();
+        self.finished = self.reached_end || token.is_err();
+        Some(token)
+    }
+}
+
+pub struct QueryLexerStream<R> {
+    driver: QueryLexerDriver<QueryLexerReader<R>>,
+}
+
+impl<R: std::io::BufRead> QueryLexerStream<R> {
+    pub fn new(reader: R) -> Self {
+        let reader = QueryLexerReader {
+            reader,
+            buffer: vec![],
+            buffer_start_pos: 0,
+            reached_eof: false,
+        };
+        Self {
+            driver: QueryLexerDriver::new(reader),
+        }
+    }
+
+    pub fn with_tab_width(mut self, tab_width: usize) -> Self {
+        self.driver.tab_width = tab_width.max(1);
+        self
+    }
+
+    pub fn with_column_unit(mut self, column_unit: QueryLexerColumnUnit) -> Self {
+        self.driver.column_unit = column_unit;
+        self
+    }
+
+    pub fn next_token(&mut self) -> Result<crate::syntax::Token, QueryLexerError> {
+        self.driver.next_token()
+    }
+
+    pub fn peek_token(&mut self) -> Result<&crate::syntax::Token, QueryLexerError> {
+        self.driver.peek_token()
+    }
+
+    pub fn tokenize_all(&mut self) -> Result<Vec<crate::syntax::Token>, QueryLexerError> {
+        self.by_ref().collect()
+    }
+
+    pub fn get_lexeme(&self, token: &crate::syntax::Token) -> Option<&str> {
+        let span = token.span();
+        let reader = &self.driver.input;
+        let start_idx = span.start_pos().checked_sub(reader.buffer_start_pos)?;
+        let end_idx = span.end_pos() - reader.buffer_start_pos;
+        str::from_utf8(reader.buffer.get(start_idx..end_idx)?).ok()
+    }
+}
+
+impl<R: std::io::BufRead> Iterator for QueryLexerStream<R> {
+    type Item = Result<crate::syntax::Token, QueryLexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.driver.next()
+    }
+}
+
+/// Input read from a `std::io::BufRead`, of which only the current token is kept.
+struct QueryLexerReader<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    buffer_start_pos: usize,
+    reached_eof: bool,
+}
+
+impl<R: std::io::BufRead> QueryLexerReader<R> {
+    fn fill_buffer(&mut self, start_pos: usize) -> Result<(), std::io::Error> {
+        // Bytes before the current token are never backtracked to, hence they can be discarded.
+        _ = self.buffer.drain(..start_pos - self.buffer_start_pos);
+        self.buffer_start_pos = start_pos;
+        let chunk = self.reader.fill_buf()?;
+        if chunk.is_empty() {
+            self.reached_eof = true;
+        }
+        self.buffer.extend_from_slice(chunk);
+        let chunk_length = chunk.len();
+        self.reader.consume(chunk_length);
+        Ok(())
+    }
+}
+
+impl<R: std::io::BufRead> QueryLexerInput for QueryLexerReader<R> {
+    fn char_at(&mut self, pos: usize, start_pos: usize) -> Result<Option<char>, QueryLexerErrorKind> {
+        let mut idx = pos - self.buffer_start_pos;
+        while self.buffer.len() < idx + 4 && !self.reached_eof {
+            self.fill_buffer(start_pos).map_err(QueryLexerErrorKind::Io)?;
+            idx = pos - self.buffer_start_pos;
+        }
+        let bytes = &self.buffer[idx..self.buffer.len().min(idx + 4)];
+        let valid_bytes = match str::from_utf8(bytes) {
+            Ok(valid_bytes) => valid_bytes,
+            Err(error) => str::from_utf8(&bytes[..error.valid_up_to()]).unwrap(),
+        };
+        match valid_bytes.chars().next() {
+            Some(c) => Ok(Some(c)),
+            None if bytes.is_empty() => Ok(None),
+            None => Err(QueryLexerErrorKind::InvalidUtf8),
+        }
+    }
+
+    fn slice(&self, start_pos: usize, end_pos: usize) -> &str {
+        let buffer_start_pos = self.buffer_start_pos;
+        let bytes = &self.buffer[start_pos - buffer_start_pos..end_pos - buffer_start_pos];
+        str::from_utf8(bytes).unwrap()
+    }
+}
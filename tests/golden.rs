@@ -0,0 +1,109 @@
+//! Compares the generated lexers with the golden files in `tests/fixtures`.
+//!
+//! After an intended change to the generated code, regenerate the golden files with:
+//!
+//! ```sh
+//! JLEK_BLESS=1 cargo test --test golden
+//! ```
+
+mod common;
+
+use std::path::Path;
+
+use jlek::{Backend, Construction, Options, Separators, TokenSpec};
+
+fn check(file_name: &str, options: Options) {
+    check_token_specs(file_name, options, common::token_specs());
+}
+
+fn check_token_specs(file_name: &str, options: Options, token_specs: Vec<TokenSpec>) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(file_name);
+    let generated = options.generate_to_string(&token_specs).unwrap();
+    if std::env::var_os("JLEK_BLESS").is_some() {
+        std::fs::write(&path, generated).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        generated == expected,
+        "{} is out of date, rerun with `JLEK_BLESS=1` if the change is intended",
+        path.display()
+    );
+}
+
+#[test]
+fn jjik() {
+    check("jjik_lexer.rs", Options::new());
+}
+
+#[test]
+fn table() {
+    check("table_lexer.rs", Options::new().standalone(true));
+}
+
+#[test]
+fn direct_coded() {
+    check(
+        "direct_coded_lexer.rs",
+        Options::new()
+            .standalone(true)
+            .backend(Backend::DirectCoded),
+    );
+}
+
+#[test]
+fn no_std() {
+    let options = Options::new().standalone(true).no_std(true);
+    let generated = options.generate_to_string(&common::token_specs()).unwrap();
+    assert!(!generated.contains("std::"));
+    // The `tests/no_std` workspace member builds the fixture without the standard library.
+    check("no_std_lexer.rs", options);
+}
+
+#[test]
+fn error_recovery() {
+    check(
+        "recovery_lexer.rs",
+        Options::new().standalone(true).error_recovery(true),
+    );
+}
+
+#[test]
+fn separators() {
+    let mut token_specs = common::token_specs();
+    token_specs.push(TokenSpec::new("Newline".to_string(), "\\n".to_string()));
+    check_token_specs(
+        "separators_lexer.rs",
+        Options::new()
+            .standalone(true)
+            .separators(Separators::WhitespaceExcept("\\n".to_string())),
+        token_specs,
+    );
+}
+
+#[test]
+fn renamed() {
+    check(
+        "renamed_lexer.rs",
+        Options::new()
+            .symbol_module("crate::syntax")
+            .terminal_name("Token")
+            .terminal_class_name("TokenKind")
+            .span_name("Location")
+            .lexer_name("QueryLexer"),
+    );
+}
+
+#[test]
+fn deterministic() {
+    for construction in [Construction::FollowPos, Construction::Thompson] {
+        let options = Options::new().construction(construction);
+        let token_specs = common::token_specs();
+        assert_eq!(
+            options.generate_to_string(&token_specs).unwrap(),
+            options.generate_to_string(&token_specs).unwrap()
+        );
+    }
+}
@@ -0,0 +1,32 @@
+//! Checks that differently named lexers generated into `tests/fixtures` can share a module.
+
+/// Symbols of the renamed lexer, i.e. the ones defined by the standalone lexer under other names.
+mod syntax {
+    pub use crate::lexers::{Span as Location, Terminal as Token, TerminalClass as TokenKind};
+}
+
+#[allow(dead_code)]
+mod lexers {
+    include!("fixtures/table_lexer.rs");
+    include!("fixtures/renamed_lexer.rs");
+}
+
+#[test]
+fn shared_module() {
+    let source = "if iffy 123 x9 * -> - 'ab' bc a\n  Foo";
+    let tokens = lexers::Lexer::from_source_str(source)
+        .tokenize_all()
+        .unwrap();
+    let query_tokens = lexers::QueryLexer::from_source_str(source)
+        .tokenize_all()
+        .unwrap();
+    assert_eq!(tokens, query_tokens);
+
+    let error = lexers::QueryLexer::from_source_str("$")
+        .next_token()
+        .unwrap_err();
+    assert!(matches!(
+        error.kind(),
+        lexers::QueryLexerErrorKind::UnexpectedChar('$')
+    ));
+}
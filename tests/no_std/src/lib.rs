@@ -0,0 +1,11 @@
+//! Builds the lexer generated with `Options::no_std` in a `#![no_std]` crate, which fails should
+//! the lexer refer to `std`. The lexer itself is checked against its generator by
+//! `tests/golden.rs`.
+
+#![no_std]
+
+extern crate alloc;
+
+pub mod lexer {
+    include!("../../fixtures/no_std_lexer.rs");
+}